@@ -1,37 +1,347 @@
-fn part1(input: &str) -> u32 {
-    input
-        .split("\n\n")
-        .map(|elf_load| {
-            elf_load
-                .lines()
-                .map(|line| line.parse::<u32>().unwrap())
-                .sum()
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::io::BufRead;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+enum CalorieError {
+    #[error("line {line}: {text:?} is not a calorie count")]
+    Malformed { line: usize, text: String },
+    #[error("elf {elf}: calorie total overflows")]
+    Overflow { elf: usize },
+    #[error("no elves in input")]
+    NoElves,
+}
+
+// Everything one elf is carrying, as listed in the input.
+#[derive(Debug, Clone, PartialEq)]
+struct Elf {
+    number: usize,
+    items: Vec<u32>,
+}
+
+impl Elf {
+    pub fn total(&self) -> Result<u64, CalorieError> {
+        self.items
+            .iter()
+            .try_fold(0u64, |total, item| total.checked_add(*item as u64))
+            .ok_or(CalorieError::Overflow { elf: self.number })
+    }
+
+    pub fn largest(&self) -> Option<u32> {
+        self.items.iter().copied().max()
+    }
+}
+
+// Parse a single calorie count, ignoring surrounding whitespace (including a trailing '\r').
+// `line` is 1-based and only used for reporting.
+fn parse_item(line: usize, text: &str) -> Result<u32, CalorieError> {
+    text.trim().parse().map_err(|_| CalorieError::Malformed {
+        line,
+        text: text.trim_end_matches(['\r', '\n']).to_string(),
+    })
+}
+
+// Blank (or whitespace only) lines separate elves; runs of them don't create empty elves.
+fn parse_elves(input: &str) -> Result<Vec<Elf>, CalorieError> {
+    let mut elves = vec![];
+    let mut items = vec![];
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !items.is_empty() {
+                elves.push(Elf {
+                    number: elves.len() + 1,
+                    items: std::mem::take(&mut items),
+                });
+            }
+            continue;
+        }
+        items.push(parse_item(i + 1, line)?);
+    }
+    if !items.is_empty() {
+        elves.push(Elf {
+            number: elves.len() + 1,
+            items,
+        });
+    }
+    Ok(elves)
+}
+
+// One row per elf: number, item count, total and largest item.
+fn summary_table(elves: &[Elf]) -> Result<String, CalorieError> {
+    let mut table = format!(
+        "{:>6} {:>6} {:>10} {:>10}\n",
+        "elf", "items", "total", "largest"
+    );
+    for elf in elves {
+        table += &format!(
+            "{:>6} {:>6} {:>10} {:>10}\n",
+            elf.number,
+            elf.items.len(),
+            elf.total()?,
+            elf.largest().unwrap_or(0)
+        );
+    }
+    Ok(table)
+}
+
+// The total calories carried by one elf. Elves are numbered from 1 in input order. Ordered by
+// calories first so the biggest load compares greatest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct ElfLoad {
+    calories: u64,
+    elf: usize,
+}
+
+fn parse_loads(input: &str) -> Result<Vec<ElfLoad>, CalorieError> {
+    parse_elves(input)?
+        .iter()
+        .map(|elf| {
+            Ok(ElfLoad {
+                calories: elf.total()?,
+                elf: elf.number,
+            })
         })
-        .max()
-        .unwrap()
-}
-
-fn part2(input: &str) -> u32 {
-    let mut per_elf: Vec<u32> = input
-        .split("\n\n")
-        .map(|elf_load| {
-            elf_load
-                .lines()
-                .map(|line| line.parse::<u32>().unwrap())
-                .sum()
+        .collect()
+}
+
+// Keeps the k largest items pushed so far. Only ever holds k items at once.
+struct TopK<T: Ord> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        TopK {
+            k,
+            // k comes from the user, so let the heap grow as needed instead of reserving k.
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.heap.push(Reverse(item));
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    // Largest first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // Ascending order of Reverse is descending order of T.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|r| r.0)
+            .collect()
+    }
+}
+
+// The k largest items, largest first.
+fn top_k<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    let mut top = TopK::new(k);
+    for item in items {
+        top.push(item);
+    }
+    top.into_sorted_vec()
+}
+
+// Read elf loads line by line, keeping only the running top k. Windows line endings and trailing
+// whitespace are ignored, and any number of blank lines separates elves.
+fn stream_top_k<R: BufRead>(mut reader: R, k: usize) -> std::io::Result<Vec<ElfLoad>> {
+    let invalid = |e: CalorieError| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    let mut top = TopK::new(k);
+    let mut line = String::new();
+    let (mut line_number, mut elf) = (0, 0);
+    let mut current: Option<u64> = None;
+    loop {
+        line.clear();
+        let eof = reader.read_line(&mut line)? == 0;
+        line_number += 1;
+        let trimmed = line.trim();
+        if eof || trimmed.is_empty() {
+            if let Some(calories) = current.take() {
+                elf += 1;
+                top.push(ElfLoad { calories, elf });
+            }
+            if eof {
+                break;
+            }
+            continue;
+        }
+        let item = parse_item(line_number, &line).map_err(invalid)?;
+        current = Some(
+            current
+                .unwrap_or(0)
+                .checked_add(item as u64)
+                .ok_or_else(|| invalid(CalorieError::Overflow { elf: elf + 1 }))?,
+        );
+    }
+    Ok(top.into_sorted_vec())
+}
+
+#[derive(Debug, PartialEq)]
+struct CalorieStats {
+    elves: usize,
+    min: u64,
+    max: u64,
+    mean: f64,
+    median: f64,
+}
+
+impl CalorieStats {
+    pub fn new(loads: &[ElfLoad]) -> Option<Self> {
+        let sorted = sorted_calories(loads);
+        let n = sorted.len();
+        if n == 0 {
+            return None;
+        }
+        let total: u64 = sorted.iter().sum();
+        let median = if n % 2 == 1 {
+            sorted[n / 2] as f64
+        } else {
+            (sorted[n / 2 - 1] as f64 + sorted[n / 2] as f64) / 2.0
+        };
+        Some(CalorieStats {
+            elves: n,
+            min: sorted[0],
+            max: sorted[n - 1],
+            mean: total as f64 / n as f64,
+            median,
         })
-        .collect();
-    per_elf.sort_by_cached_key(|e| std::cmp::Reverse(*e));
-    per_elf[0..3].iter().sum()
+    }
+}
+
+fn sorted_calories(loads: &[ElfLoad]) -> Vec<u64> {
+    let mut sorted: Vec<u64> = loads.iter().map(|l| l.calories).collect();
+    sorted.sort_unstable();
+    sorted
+}
+
+// Nearest-rank percentile, p in 0..=100.
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() || !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.max(1) - 1])
+}
+
+// Number of elves per bucket of `width` calories, keyed by the start of the bucket.
+fn histogram(loads: &[ElfLoad], width: u64) -> BTreeMap<u64, usize> {
+    let mut buckets = BTreeMap::new();
+    for load in loads {
+        *buckets.entry(load.calories / width * width).or_insert(0) += 1;
+    }
+    buckets
+}
+
+fn part1(input: &str) -> Result<u64, CalorieError> {
+    top_k(parse_loads(input)?, 1)
+        .first()
+        .map(|l| l.calories)
+        .ok_or(CalorieError::NoElves)
+}
+
+fn part2(input: &str) -> Result<u64, CalorieError> {
+    Ok(top_k(parse_loads(input)?, 3)
+        .iter()
+        .map(|l| l.calories)
+        .sum())
+}
+
+// Usage: day-01 stats [--top K] [--bucket WIDTH]
+fn stats_main(txt: &str, args: &[String]) -> Result<(), String> {
+    let (mut k, mut width) = (3, 10000);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{arg} needs a value"))?;
+        let invalid = |_| format!("Invalid value {value:?} for {arg}");
+        match arg.as_str() {
+            "--top" => k = value.parse().map_err(invalid)?,
+            "--bucket" => width = value.parse().map_err(invalid)?,
+            other => return Err(format!("Unknown stats argument {other:?}")),
+        }
+    }
+    if width == 0 {
+        return Err("Bucket width must be positive".to_string());
+    }
+
+    let loads = parse_loads(txt).map_err(|e| e.to_string())?;
+    println!("{:?}", CalorieStats::new(&loads).ok_or("No elves")?);
+    let sorted = sorted_calories(&loads);
+    for p in [25.0, 50.0, 75.0, 90.0, 99.0] {
+        println!("p{p}: {}", percentile(&sorted, p).unwrap());
+    }
+    println!("Top {k}:");
+    for load in top_k(loads.iter().copied(), k) {
+        println!("  elf {}: {}", load.elf, load.calories);
+    }
+    for (start, count) in histogram(&loads, width) {
+        println!("{:>8}..{:<8} {}", start, start + width, "#".repeat(count));
+    }
+    Ok(())
+}
+
+// Usage: day-01 stream [K] [PATH]
+// Reads from stdin when PATH is "-", and from the puzzle input when it is omitted.
+fn stream_main(args: &[String]) -> Result<(), String> {
+    let k = args
+        .first()
+        .map_or(Ok(3), |k| k.parse())
+        .map_err(|e| format!("Invalid k: {e}"))?;
+    let winners = match args.get(1).map(String::as_str) {
+        Some("-") => stream_top_k(std::io::stdin().lock(), k),
+        path => {
+            let path = path.map_or_else(
+                || std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/day-01.txt"),
+                std::path::PathBuf::from,
+            );
+            let file = std::fs::File::open(&path).map_err(|e| format!("{path:?}: {e}"))?;
+            stream_top_k(std::io::BufReader::new(file), k)
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    for load in winners {
+        println!("elf {}: {}", load.elf, load.calories);
+    }
+    Ok(())
 }
 
 fn main() {
-    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.extend(vec!["data", "day-01.txt"]);
-    println!("{:?}", path);
-    let txt = std::fs::read_to_string(path).unwrap();
-    println!("{:?}", part1(&txt));
-    println!("{:?}", part2(&txt));
+    let read_input = || {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.extend(vec!["data", "day-01.txt"]);
+        println!("{:?}", path);
+        std::fs::read_to_string(path).unwrap()
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("stream") => {
+            if let Err(e) = stream_main(&args[1..]) {
+                println!("{e}");
+            }
+        }
+        // Usage: day-01 summary
+        Some("summary") => match parse_elves(&read_input()).and_then(|elves| summary_table(&elves))
+        {
+            Ok(table) => print!("{table}"),
+            Err(e) => println!("{e}"),
+        },
+        Some("stats") => {
+            if let Err(e) = stats_main(&read_input(), &args[1..]) {
+                println!("{e}");
+            }
+        }
+        _ => {
+            let txt = read_input();
+            println!("{:?}", part1(&txt));
+            println!("{:?}", part2(&txt));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -57,6 +367,162 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(24000, part1(INPUT1));
+        assert_eq!(Ok(24000), part1(INPUT1));
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(Ok(45000), part2(INPUT1));
+    }
+
+    #[test]
+    fn test_top_k() {
+        assert_eq!(vec![9, 7], top_k([3, 9, 1, 7, 5], 2));
+        assert_eq!(vec![3, 1], top_k([1, 3], 5));
+        assert!(top_k([1, 2], 0).is_empty());
+        assert_eq!(vec![2, 1], top_k([1, 2], usize::MAX));
+        assert_eq!(
+            5,
+            stream_top_k(INPUT1.as_bytes(), usize::MAX).unwrap().len()
+        );
+
+        let loads = parse_loads(INPUT1).unwrap();
+        assert_eq!(
+            vec![
+                ElfLoad {
+                    calories: 24000,
+                    elf: 4
+                },
+                ElfLoad {
+                    calories: 11000,
+                    elf: 3
+                }
+            ],
+            top_k(loads, 2)
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let loads = parse_loads(INPUT1).unwrap();
+        assert_eq!(
+            Some(CalorieStats {
+                elves: 5,
+                min: 4000,
+                max: 24000,
+                mean: 11000.0,
+                median: 10000.0,
+            }),
+            CalorieStats::new(&loads)
+        );
+        assert_eq!(
+            Some(5000.0),
+            CalorieStats::new(&loads[..2]).map(|s| s.median)
+        );
+        assert_eq!(None, CalorieStats::new(&[]));
+
+        let sorted = sorted_calories(&loads);
+        assert_eq!(Some(4000), percentile(&sorted, 0.0));
+        assert_eq!(Some(10000), percentile(&sorted, 50.0));
+        assert_eq!(Some(11000), percentile(&sorted, 80.0));
+        assert_eq!(Some(24000), percentile(&sorted, 100.0));
+        assert_eq!(None, percentile(&sorted, 101.0));
+
+        assert_eq!(
+            BTreeMap::from([(0, 1), (5000, 1), (10000, 2), (20000, 1)]),
+            histogram(&loads, 5000)
+        );
+    }
+
+    #[test]
+    fn test_stream_top_k() {
+        let winners = stream_top_k(INPUT1.as_bytes(), 3).unwrap();
+        assert_eq!(top_k(parse_loads(INPUT1).unwrap(), 3), winners);
+        assert_eq!(
+            vec![4, 3, 5],
+            winners.iter().map(|l| l.elf).collect::<Vec<_>>()
+        );
+
+        let crlf = "\r\n1000 \r\n2000\r\n\r\n\r\n5000\t\r\n\r\n4000";
+        assert_eq!(
+            vec![
+                ElfLoad {
+                    calories: 5000,
+                    elf: 2
+                },
+                ElfLoad {
+                    calories: 4000,
+                    elf: 3
+                }
+            ],
+            stream_top_k(crlf.as_bytes(), 2).unwrap()
+        );
+
+        let err = stream_top_k("1\n\nabc\n".as_bytes(), 1).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert_eq!("line 3: \"abc\" is not a calorie count", err.to_string());
+        assert!(stream_top_k("4294967296\n".as_bytes(), 1).is_err());
+        // Totals are wider than individual items.
+        assert_eq!(
+            4294967296,
+            stream_top_k("4294967295\n1\n".as_bytes(), 1).unwrap()[0].calories
+        );
+    }
+
+    #[test]
+    fn test_parse_elves() {
+        let elves = parse_elves("1\r\n2\r\n\r\n\r\n  \r\n30\n").unwrap();
+        assert_eq!(
+            vec![
+                Elf {
+                    number: 1,
+                    items: vec![1, 2]
+                },
+                Elf {
+                    number: 2,
+                    items: vec![30]
+                }
+            ],
+            elves
+        );
+        assert_eq!(Ok(3), elves[0].total());
+        assert_eq!(Some(30), elves[1].largest());
+
+        assert_eq!(
+            Err(CalorieError::Malformed {
+                line: 4,
+                text: "12x".to_string()
+            }),
+            parse_elves("1\n\n2\n12x\n")
+        );
+        assert_eq!(
+            Err(CalorieError::Malformed {
+                line: 1,
+                text: "-5".to_string()
+            }),
+            part1("-5\n")
+        );
+        assert_eq!(Err(CalorieError::NoElves), part1("\n\n"));
+
+        let big = Elf {
+            number: 7,
+            items: vec![u32::MAX; 3],
+        };
+        assert_eq!(Ok(3 * u32::MAX as u64), big.total());
+    }
+
+    #[test]
+    fn test_summary_table() {
+        let table = summary_table(&parse_elves(INPUT1).unwrap()).unwrap();
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(6, rows.len());
+        assert_eq!(
+            vec!["elf", "items", "total", "largest"],
+            rows[0].split_whitespace().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["4", "3", "24000", "9000"],
+            rows[4].split_whitespace().collect::<Vec<_>>()
+        );
     }
 }
@@ -1,6 +1,11 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::str::FromStr;
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+use itertools::Itertools;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 enum RpsMove {
     Rock,
     Paper,
@@ -15,82 +20,278 @@ enum Outcome {
 }
 
 impl RpsMove {
-    fn points(&self) -> i32 {
-        match self {
-            RpsMove::Rock => 1,
-            RpsMove::Paper => 2,
-            RpsMove::Scissors => 3,
+    const ALL: [RpsMove; 3] = [RpsMove::Rock, RpsMove::Paper, RpsMove::Scissors];
+
+    // Position of the move in `CyclicGame::rps`.
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_index(index: usize) -> Self {
+        RpsMove::ALL[index]
+    }
+}
+
+// A game where the moves form a cycle: each move beats the (n - 1) / 2 moves before it and loses to
+// the (n - 1) / 2 moves after it. With an odd number of moves every pair of different moves has a
+// winner, which covers Rock-Paper-Scissors, Rock-Paper-Scissors-Lizard-Spock and so on.
+#[derive(Debug, Clone, PartialEq)]
+struct CyclicGame {
+    moves: Cow<'static, [Cow<'static, str>]>,
+}
+
+impl CyclicGame {
+    // The puzzle's game, in the order of `RpsMove::ALL`.
+    pub const RPS: CyclicGame = CyclicGame {
+        moves: Cow::Borrowed(&[
+            Cow::Borrowed("Rock"),
+            Cow::Borrowed("Paper"),
+            Cow::Borrowed("Scissors"),
+        ]),
+    };
+
+    pub fn new(moves: Vec<String>) -> Result<Self, String> {
+        if moves.len().is_multiple_of(2) {
+            return Err(format!("Need an odd number of moves, got {}", moves.len()));
         }
+        let moves = moves.into_iter().map(Cow::Owned).collect();
+        Ok(CyclicGame {
+            moves: Cow::Owned(moves),
+        })
     }
 
-    fn shoot(&self, other: &Self) -> Outcome {
-        match self {
-            RpsMove::Rock => match other {
-                RpsMove::Rock => Outcome::Draw,
-                RpsMove::Paper => Outcome::Lose,
-                RpsMove::Scissors => Outcome::Win,
-            },
-            RpsMove::Paper => match other {
-                RpsMove::Rock => Outcome::Win,
-                RpsMove::Paper => Outcome::Draw,
-                RpsMove::Scissors => Outcome::Lose,
-            },
-            RpsMove::Scissors => match other {
-                RpsMove::Rock => Outcome::Lose,
-                RpsMove::Paper => Outcome::Win,
-                RpsMove::Scissors => Outcome::Draw,
-            },
+    pub fn rpsls() -> Self {
+        let moves = ["Rock", "Spock", "Paper", "Lizard", "Scissors"];
+        CyclicGame::new(moves.iter().map(|m| m.to_string()).collect()).unwrap()
+    }
+
+    // Moves simply named by their number.
+    pub fn numbered(n: usize) -> Result<Self, String> {
+        CyclicGame::new((0..n).map(|i| i.to_string()).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        &self.moves[index]
+    }
+
+    pub fn outcome(&self, mine: usize, other: usize) -> Outcome {
+        let n = self.len();
+        match (mine + n - other) % n {
+            0 => Outcome::Draw,
+            d if d <= n / 2 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
+    }
+
+    // Every move that gets `outcome` against `other`.
+    pub fn responses(&self, other: usize, outcome: Outcome) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(move |mine| self.outcome(*mine, other) == outcome)
+    }
+}
+
+// Points for a round: the points of the move played plus the points of the outcome.
+#[derive(Debug, Clone, PartialEq)]
+struct Scoring {
+    move_points: Cow<'static, [i32]>,
+    lose: i32,
+    draw: i32,
+    win: i32,
+}
+
+impl Scoring {
+    // `standard` scoring for `CyclicGame::RPS`.
+    pub const RPS: Scoring = Scoring {
+        move_points: Cow::Borrowed(&[1, 2, 3]),
+        lose: 0,
+        draw: 3,
+        win: 6,
+    };
+
+    // The puzzle's scoring: 1 point for the first move, 2 for the second, etc. and 0/3/6 for
+    // losing/drawing/winning.
+    pub fn standard(n_moves: usize) -> Self {
+        Scoring {
+            move_points: (1..=n_moves as i32).collect::<Vec<_>>().into(),
+            lose: 0,
+            draw: 3,
+            win: 6,
+        }
+    }
+
+    pub fn score(&self, game: &CyclicGame, other: usize, mine: usize) -> i32 {
+        let outcome_points = match game.outcome(mine, other) {
+            Outcome::Lose => self.lose,
+            Outcome::Draw => self.draw,
+            Outcome::Win => self.win,
+        };
+        outcome_points + self.move_points[mine]
+    }
+
+    // The highest scoring move that gets `outcome` against `other`, if any does. A game of a
+    // single move can only draw.
+    pub fn best_response(
+        &self,
+        game: &CyclicGame,
+        other: usize,
+        outcome: Outcome,
+    ) -> Option<usize> {
+        game.responses(other, outcome)
+            .max_by_key(|mine| self.move_points[*mine])
+    }
+}
+
+// Usage: day-02 table [rps|rpsls|N]
+// Prints who wins each pairing of moves and the best move for each wanted outcome.
+fn table_main(game: &str) -> Result<(), String> {
+    let game = match game {
+        "rps" => CyclicGame::RPS,
+        "rpsls" => CyclicGame::rpsls(),
+        n => CyclicGame::numbered(n.parse().map_err(|_| format!("Unknown game {n:?}"))?)?,
+    };
+    let scoring = Scoring::standard(game.len());
+    for mine in 0..game.len() {
+        for other in 0..game.len() {
+            let outcome = game.outcome(mine, other);
+            println!("{} vs {}: {outcome:?}", game.name(mine), game.name(other));
+        }
+    }
+    for other in 0..game.len() {
+        for outcome in [Outcome::Lose, Outcome::Draw, Outcome::Win] {
+            let mine = scoring.best_response(&game, other, outcome);
+            println!(
+                "{outcome:?} against {}: {}",
+                game.name(other),
+                mine.map_or("none", |mine| game.name(mine))
+            );
         }
     }
+    Ok(())
 }
 
+// Parse the opponent's column of a strategy guide. What X/Y/Z mean is up to `ColumnTwo`.
 impl FromStr for RpsMove {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "A" | "X" => Ok(RpsMove::Rock),
-            "B" | "Y" => Ok(RpsMove::Paper),
-            "C" | "Z" => Ok(RpsMove::Scissors),
-            _ => Err("Invalid RPS move".to_string()),
+            "A" => Ok(RpsMove::Rock),
+            "B" => Ok(RpsMove::Paper),
+            "C" => Ok(RpsMove::Scissors),
+            _ => Err(format!("Invalid opponent move {s:?}")),
         }
     }
 }
 
-impl FromStr for Outcome {
+fn points(other_move: RpsMove, my_move: RpsMove) -> i32 {
+    Scoring::RPS.score(&CyclicGame::RPS, other_move.index(), my_move.index())
+}
+
+// How to read the X/Y/Z column of a strategy guide.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnTwo {
+    // X, Y and Z are my moves.
+    Moves([RpsMove; 3]),
+    // X, Y and Z are the outcomes I should aim for.
+    Outcomes([Outcome; 3]),
+}
+
+// Parse the meaning of X, Y and Z in order, either as moves ("RPS", "SRP", ...) or as outcomes
+// ("LDW", "WLD", ...).
+impl FromStr for ColumnTwo {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "X" => Ok(Outcome::Lose),
-            "Y" => Ok(Outcome::Draw),
-            "Z" => Ok(Outcome::Win),
-            _ => Err("Invalid RPS outcome".to_string()),
+        let invalid = || format!("Invalid column mapping {s:?}");
+        let letters: Vec<char> = s.chars().collect();
+        let letters: [char; 3] = letters.try_into().map_err(|_| invalid())?;
+        let mut sorted = letters;
+        sorted.sort_unstable();
+        match sorted {
+            ['P', 'R', 'S'] => Ok(ColumnTwo::Moves(letters.map(|c| match c {
+                'R' => RpsMove::Rock,
+                'P' => RpsMove::Paper,
+                _ => RpsMove::Scissors,
+            }))),
+            ['D', 'L', 'W'] => Ok(ColumnTwo::Outcomes(letters.map(|c| match c {
+                'L' => Outcome::Lose,
+                'D' => Outcome::Draw,
+                _ => Outcome::Win,
+            }))),
+            _ => Err(invalid()),
         }
     }
 }
 
-fn points(other_move: RpsMove, my_move: RpsMove) -> i32 {
-    let cmp_points = match my_move.shoot(&other_move) {
-        Outcome::Lose => 0,
-        Outcome::Draw => 3,
-        Outcome::Win => 6,
-    };
-    cmp_points + my_move.points()
+// Runtime rules for interpreting and scoring a strategy guide.
+#[derive(Debug, Clone, PartialEq)]
+struct GuideRules {
+    column_two: ColumnTwo,
+    scoring: Scoring,
+}
+
+impl GuideRules {
+    // X/Y/Z are Rock/Paper/Scissors.
+    pub fn part1() -> Self {
+        GuideRules {
+            column_two: ColumnTwo::Moves(RpsMove::ALL),
+            scoring: Scoring::RPS,
+        }
+    }
+
+    // X/Y/Z are Lose/Draw/Win.
+    pub fn part2() -> Self {
+        GuideRules {
+            column_two: ColumnTwo::Outcomes([Outcome::Lose, Outcome::Draw, Outcome::Win]),
+            scoring: Scoring::RPS,
+        }
+    }
+
+    // Parse a guide into a vec of (other_move, my_move), one entry per line.
+    pub fn to_moves(&self, txt: &str) -> Result<Vec<(RpsMove, RpsMove)>, String> {
+        txt.lines()
+            .map(|line| {
+                let (other, mine) = line
+                    .split_once(' ')
+                    .ok_or(format!("Invalid guide line {line:?}"))?;
+                let other_move: RpsMove = other.parse()?;
+                let column = match mine {
+                    "X" => 0,
+                    "Y" => 1,
+                    "Z" => 2,
+                    _ => return Err(format!("Invalid guide symbol {mine:?}")),
+                };
+                let my_move = match self.column_two {
+                    ColumnTwo::Moves(moves) => moves[column],
+                    ColumnTwo::Outcomes(outcomes) => RpsMove::from_index(
+                        self.scoring
+                            .best_response(&CyclicGame::RPS, other_move.index(), outcomes[column])
+                            .ok_or(format!("No move gets {:?}", outcomes[column]))?,
+                    ),
+                };
+                Ok((other_move, my_move))
+            })
+            .collect()
+    }
+
+    pub fn score(&self, txt: &str) -> Result<i32, String> {
+        Ok(self
+            .to_moves(txt)?
+            .iter()
+            .map(|(other, mine)| {
+                self.scoring
+                    .score(&CyclicGame::RPS, other.index(), mine.index())
+            })
+            .sum())
+    }
 }
 
 fn to_moves1(txt: &str) -> Vec<(RpsMove, RpsMove)> {
     // Inputs come in the form (other_move, my_move) and we parse this into a vec (entry per line).
-    txt.lines()
-        .map(|line| {
-            let moves: Vec<RpsMove> = line
-                .split(' ')
-                .map(|s| s.parse::<RpsMove>().unwrap())
-                .collect();
-            assert_eq!(moves.len(), 2);
-            (moves[0], moves[1])
-        })
-        .collect()
+    GuideRules::part1().to_moves(txt).unwrap()
 }
 
 fn part1(txt: &str) -> i32 {
@@ -101,32 +302,9 @@ fn part1(txt: &str) -> i32 {
 }
 
 fn to_moves2(txt: &str) -> Vec<(RpsMove, RpsMove)> {
-    // Inputs come in the form (other_move, outcme) and we parse this into a vec of (other_move,
+    // Inputs come in the form (other_move, outcome) and we parse this into a vec of (other_move,
     // my_move). (one entry per line).
-    txt.lines()
-        .map(|line| {
-            let inputs: Vec<_> = line.split(' ').collect();
-            assert_eq!(inputs.len(), 2);
-            let other_move: RpsMove = inputs[0].parse().unwrap();
-            let outcome: Outcome = inputs[1].parse().unwrap();
-
-            let my_move = match outcome {
-                Outcome::Lose => match other_move {
-                    RpsMove::Rock => RpsMove::Scissors,
-                    RpsMove::Paper => RpsMove::Rock,
-                    RpsMove::Scissors => RpsMove::Paper,
-                },
-                Outcome::Draw => other_move,
-                Outcome::Win => match other_move {
-                    RpsMove::Rock => RpsMove::Paper,
-                    RpsMove::Paper => RpsMove::Scissors,
-                    RpsMove::Scissors => RpsMove::Rock,
-                },
-            };
-
-            (other_move, my_move)
-        })
-        .collect()
+    GuideRules::part2().to_moves(txt).unwrap()
 }
 
 fn part2(txt: &str) -> i32 {
@@ -136,14 +314,441 @@ fn part2(txt: &str) -> i32 {
         .sum()
 }
 
+// Parse 3 comma separated numbers, e.g. "0,3,6".
+fn parse_points(s: &str) -> Result<[i32; 3], String> {
+    let points = s
+        .split(',')
+        .map(|p| p.trim().parse())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| format!("Invalid points {s:?}"))?;
+    points
+        .try_into()
+        .map_err(|_| format!("Expected 3 points, got {s:?}"))
+}
+
+// Usage: day-02 guide MAPPING [--move-points R,P,S] [--outcome-points L,D,W]
+// where MAPPING is what X, Y and Z mean, e.g. "RPS" for part 1 or "LDW" for part 2.
+fn guide_main(txt: &str, args: &[String]) -> Result<i32, String> {
+    let mut args = args.iter();
+    let mapping = args.next().ok_or("Missing column mapping")?;
+    let mut rules = GuideRules {
+        column_two: mapping.parse()?,
+        scoring: Scoring::RPS,
+    };
+    while let Some(arg) = args.next() {
+        if arg != "--move-points" && arg != "--outcome-points" {
+            return Err(format!("Unknown guide argument {arg:?}"));
+        }
+        let points = parse_points(args.next().ok_or(format!("{arg} needs points"))?)?;
+        if arg == "--move-points" {
+            rules.scoring.move_points = points.to_vec().into();
+        } else {
+            [rules.scoring.lose, rules.scoring.draw, rules.scoring.win] = points;
+        }
+    }
+    rules.score(txt)
+}
+
+// Score the guide for every way of mapping X, Y and Z onto the three moves.
+fn permutation_scores(txt: &str) -> Result<Vec<([RpsMove; 3], i32)>, String> {
+    RpsMove::ALL
+        .into_iter()
+        .permutations(3)
+        .map(|moves| {
+            let moves: [RpsMove; 3] = moves.try_into().unwrap();
+            let rules = GuideRules {
+                column_two: ColumnTwo::Moves(moves),
+                scoring: Scoring::RPS,
+            };
+            Ok((moves, rules.score(txt)?))
+        })
+        .collect()
+}
+
+// Small deterministic PRNG (splitmix64) so that simulations are reproducible from a seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform-enough index in 0..n for our purposes.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// The move that beats `other`. Rock-Paper-Scissors always has exactly one.
+fn beats(other: RpsMove) -> RpsMove {
+    let game = CyclicGame::RPS;
+    let mut winners = game.responses(other.index(), Outcome::Win);
+    RpsMove::from_index(winners.next().unwrap())
+}
+
+// A tournament player. Picks its next move only from what the opponent has played so far.
+trait Strategy {
+    fn name(&self) -> &str;
+    fn next_move(&self, opponent_history: &[RpsMove], rng: &mut SplitMix64) -> RpsMove;
+}
+
+// Plays a fixed sequence (e.g. a column from a guide), repeating it once exhausted.
+struct FixedSequence {
+    name: String,
+    moves: Vec<RpsMove>,
+}
+
+impl Strategy for FixedSequence {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn next_move(&self, opponent_history: &[RpsMove], _rng: &mut SplitMix64) -> RpsMove {
+        self.moves[opponent_history.len() % self.moves.len()]
+    }
+}
+
+struct RandomMoves;
+
+impl Strategy for RandomMoves {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn next_move(&self, _opponent_history: &[RpsMove], rng: &mut SplitMix64) -> RpsMove {
+        RpsMove::from_index(rng.below(RpsMove::ALL.len()))
+    }
+}
+
+// Beats whatever the opponent has played most often. Ties go to the earlier move in ALL.
+struct BeatMostFrequent;
+
+impl Strategy for BeatMostFrequent {
+    fn name(&self) -> &str {
+        "beat-most-frequent"
+    }
+
+    fn next_move(&self, opponent_history: &[RpsMove], rng: &mut SplitMix64) -> RpsMove {
+        if opponent_history.is_empty() {
+            return RandomMoves.next_move(opponent_history, rng);
+        }
+        let mut counts = [0; 3];
+        for m in opponent_history {
+            counts[m.index()] += 1;
+        }
+        let most = (0..counts.len()).rev().max_by_key(|&i| counts[i]).unwrap();
+        beats(RpsMove::from_index(most))
+    }
+}
+
+struct BeatLast;
+
+impl Strategy for BeatLast {
+    fn name(&self) -> &str {
+        "beat-last"
+    }
+
+    fn next_move(&self, opponent_history: &[RpsMove], rng: &mut SplitMix64) -> RpsMove {
+        match opponent_history.last() {
+            Some(last) => beats(*last),
+            None => RandomMoves.next_move(opponent_history, rng),
+        }
+    }
+}
+
+// An n-gram model of the opponent's moves: for every context of up to `order` consecutive moves,
+// how often each move followed it. Counts are updated as each move is observed, so predicting never
+// rescans the history.
+#[derive(Debug, Clone)]
+struct NgramModel {
+    order: usize,
+    history: Vec<RpsMove>,
+    counts: HashMap<Vec<RpsMove>, [usize; 3]>,
+}
+
+impl NgramModel {
+    pub fn new(order: usize) -> Self {
+        NgramModel {
+            order,
+            history: Vec::new(),
+            counts: HashMap::new(),
+        }
+    }
+
+    // Count `next` as following each of the last 0..=order moves.
+    pub fn observe(&mut self, next: RpsMove) {
+        let n = self.history.len();
+        for k in 0..=self.order.min(n) {
+            let context = &self.history[n - k..];
+            match self.counts.get_mut(context) {
+                Some(counts) => counts[next.index()] += 1,
+                None => {
+                    let mut counts = [0; 3];
+                    counts[next.index()] = 1;
+                    self.counts.insert(context.to_vec(), counts);
+                }
+            }
+        }
+        self.history.push(next);
+    }
+
+    // Predict the next move from the longest context of the last moves that has been seen before,
+    // falling back to shorter contexts down to plain move frequency. Ties go to the earlier move.
+    pub fn predict(&self) -> Option<RpsMove> {
+        let n = self.history.len();
+        (0..=self.order.min(n)).rev().find_map(|k| {
+            let counts = self.counts.get(&self.history[n - k..])?;
+            let most = (0..counts.len()).rev().max_by_key(|&i| counts[i])?;
+            Some(RpsMove::from_index(most))
+        })
+    }
+}
+
+// Plays whatever beats the n-gram prediction of the opponent's next move.
+struct MarkovPredictor {
+    name: String,
+    order: usize,
+    // The model of the history seen so far, so each round only adds the opponent's latest move.
+    model: RefCell<NgramModel>,
+}
+
+impl MarkovPredictor {
+    pub fn new(order: usize) -> Self {
+        MarkovPredictor {
+            name: format!("markov-{order}"),
+            order,
+            model: RefCell::new(NgramModel::new(order)),
+        }
+    }
+}
+
+impl Strategy for MarkovPredictor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn next_move(&self, opponent_history: &[RpsMove], rng: &mut SplitMix64) -> RpsMove {
+        let mut model = self.model.borrow_mut();
+        // A shorter history means a new match has started.
+        if opponent_history.len() < model.history.len() {
+            *model = NgramModel::new(self.order);
+        }
+        for other in &opponent_history[model.history.len()..] {
+            model.observe(*other);
+        }
+        match model.predict() {
+            Some(prediction) => beats(prediction),
+            None => RandomMoves.next_move(opponent_history, rng),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Record {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    points: i32,
+}
+
+impl Record {
+    fn add(&mut self, other_move: RpsMove, my_move: RpsMove) {
+        match CyclicGame::RPS.outcome(my_move.index(), other_move.index()) {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Lose => self.losses += 1,
+        }
+        self.points += points(other_move, my_move);
+    }
+
+    fn merge(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.points += other.points;
+    }
+}
+
+// Play `rounds` rounds between a and b, returning each side's record.
+fn play_match(
+    a: &dyn Strategy,
+    b: &dyn Strategy,
+    rounds: usize,
+    rng: &mut SplitMix64,
+) -> (Record, Record) {
+    let (mut a_moves, mut b_moves) = (vec![], vec![]);
+    let (mut a_record, mut b_record) = (Record::default(), Record::default());
+    for _ in 0..rounds {
+        let a_move = a.next_move(&b_moves, rng);
+        let b_move = b.next_move(&a_moves, rng);
+        a_record.add(b_move, a_move);
+        b_record.add(a_move, b_move);
+        a_moves.push(a_move);
+        b_moves.push(b_move);
+    }
+    (a_record, b_record)
+}
+
+// Round robin where every pair of strategies plays one match of `rounds` rounds. Returns the
+// combined record of each strategy, in the order given.
+fn tournament(strategies: &[Box<dyn Strategy>], rounds: usize, seed: u64) -> Vec<Record> {
+    let mut rng = SplitMix64::new(seed);
+    let mut records = vec![Record::default(); strategies.len()];
+    for (i, j) in (0..strategies.len()).tuple_combinations() {
+        let (a, b) = play_match(&*strategies[i], &*strategies[j], rounds, &mut rng);
+        records[i].merge(a);
+        records[j].merge(b);
+    }
+    records
+}
+
+// The fixed sequences from the guide plus the adaptive strategies.
+fn guide_strategies(txt: &str) -> Result<Vec<Box<dyn Strategy>>, String> {
+    let fixed = |name: &str, moves: Vec<RpsMove>| -> Box<dyn Strategy> {
+        Box::new(FixedSequence {
+            name: name.to_string(),
+            moves,
+        })
+    };
+    let part1 = GuideRules::part1().to_moves(txt)?;
+    let part2 = GuideRules::part2().to_moves(txt)?;
+    // Fixed sequences repeat the guide, so they need at least one move.
+    if part1.is_empty() {
+        return Err("The guide is empty".to_string());
+    }
+    Ok(vec![
+        fixed("opponent", part1.iter().map(|(other, _)| *other).collect()),
+        fixed("guide-part1", part1.iter().map(|(_, mine)| *mine).collect()),
+        fixed("guide-part2", part2.iter().map(|(_, mine)| *mine).collect()),
+        Box::new(RandomMoves),
+        Box::new(BeatMostFrequent),
+        Box::new(BeatLast),
+        Box::new(MarkovPredictor::new(2)),
+    ])
+}
+
+#[derive(Debug, PartialEq)]
+struct PredictionReport {
+    rounds: usize,
+    // Rounds where the opponent's move was predicted correctly.
+    correct: usize,
+    predicted: Record,
+    guide_part1: i32,
+    guide_part2: i32,
+}
+
+// Play the predictor against the guide's opponent column, one round at a time and only looking at
+// past rounds, and compare with the scores of the moves the guide prescribes.
+fn prediction_report(txt: &str, order: usize) -> Result<PredictionReport, String> {
+    let moves = GuideRules::part1().to_moves(txt)?;
+    let mut model = NgramModel::new(order);
+    let mut correct = 0;
+    let mut predicted = Record::default();
+    for (other, _) in &moves {
+        let prediction = model.predict();
+        if prediction == Some(*other) {
+            correct += 1;
+        }
+        // Before any history exists, just open with rock.
+        let mine = prediction.map_or(RpsMove::Rock, beats);
+        predicted.add(*other, mine);
+        model.observe(*other);
+    }
+    Ok(PredictionReport {
+        rounds: moves.len(),
+        correct,
+        predicted,
+        guide_part1: GuideRules::part1().score(txt)?,
+        guide_part2: GuideRules::part2().score(txt)?,
+    })
+}
+
+// Usage: day-02 tournament [--rounds N] [--seed S]
+// Rounds default to the length of the guide.
+fn tournament_main(txt: &str, args: &[String]) -> Result<(), String> {
+    let mut rounds = txt.lines().count();
+    let mut seed = 0;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{arg} needs a value"))?;
+        let invalid = |_| format!("Invalid value {value:?} for {arg}");
+        match arg.as_str() {
+            "--rounds" => rounds = value.parse().map_err(invalid)?,
+            "--seed" => seed = value.parse().map_err(invalid)?,
+            other => return Err(format!("Unknown tournament argument {other:?}")),
+        }
+    }
+
+    let strategies = guide_strategies(txt)?;
+    let records = tournament(&strategies, rounds, seed);
+    println!(
+        "{:<20} {:>8} {:>8} {:>8} {:>10}",
+        "strategy", "wins", "draws", "losses", "points"
+    );
+    for (strategy, record) in strategies.iter().zip(records) {
+        println!(
+            "{:<20} {:>8} {:>8} {:>8} {:>10}",
+            strategy.name(),
+            record.wins,
+            record.draws,
+            record.losses,
+            record.points
+        );
+    }
+    Ok(())
+}
+
 fn main() {
-    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.extend(vec!["data", "day-02.txt"]);
-    println!("{:?}", path);
-    let txt = std::fs::read_to_string(path).unwrap();
+    let read_input = || {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.extend(vec!["data", "day-02.txt"]);
+        println!("{:?}", path);
+        std::fs::read_to_string(path).unwrap()
+    };
 
-    println!("{:?}", part1(&txt));
-    println!("{:?}", part2(&txt));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("table") => {
+            if let Err(e) = table_main(args.get(1).map_or("rps", String::as_str)) {
+                println!("{e}");
+            }
+        }
+        Some("guide") => println!("{:?}", guide_main(&read_input(), &args[1..])),
+        Some("permutations") => match permutation_scores(&read_input()) {
+            Ok(scores) => {
+                for ([x, y, z], score) in scores {
+                    println!("X={x:?} Y={y:?} Z={z:?}: {score}");
+                }
+            }
+            Err(e) => println!("{e}"),
+        },
+        // Usage: day-02 predict [ORDER]
+        Some("predict") => match args.get(1).map_or(Ok(2), |order| order.parse()) {
+            Ok(order) => match prediction_report(&read_input(), order) {
+                Ok(report) => println!("{report:?}"),
+                Err(e) => println!("{e}"),
+            },
+            Err(e) => println!("Invalid order: {e}"),
+        },
+        Some("tournament") => {
+            if let Err(e) = tournament_main(&read_input(), &args[1..]) {
+                println!("{e}");
+            }
+        }
+        _ => {
+            let txt = read_input();
+            println!("{:?}", part1(&txt));
+            println!("{:?}", part2(&txt));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -194,4 +799,332 @@ C Z
     fn test_part2b() {
         assert_eq!(12, part2(INPUT));
     }
+
+    #[test]
+    fn test_rps() {
+        use RpsMove::*;
+        let game = CyclicGame::RPS;
+        let shoot = |mine: RpsMove, other: RpsMove| game.outcome(mine.index(), other.index());
+        assert_eq!(Outcome::Win, shoot(Rock, Scissors));
+        assert_eq!(Outcome::Lose, shoot(Rock, Paper));
+        assert_eq!(Outcome::Draw, shoot(Paper, Paper));
+        assert_eq!(Outcome::Win, shoot(Scissors, Paper));
+        assert_eq!(Outcome::Lose, shoot(Scissors, Rock));
+    }
+
+    #[test]
+    fn test_rpsls() {
+        let game = CyclicGame::rpsls();
+        let index = |name: &str| game.moves.iter().position(|m| m == name).unwrap();
+        let wins = [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ];
+        for (winner, loser) in wins {
+            assert_eq!(Outcome::Win, game.outcome(index(winner), index(loser)));
+            assert_eq!(Outcome::Lose, game.outcome(index(loser), index(winner)));
+        }
+
+        let to_beat_rock: Vec<&str> = game
+            .responses(index("Rock"), Outcome::Win)
+            .map(|m| game.name(m))
+            .collect();
+        assert_eq!(vec!["Spock", "Paper"], to_beat_rock);
+        // Paper is worth more points than Spock.
+        let scoring = Scoring::standard(5);
+        assert_eq!(
+            Some(index("Paper")),
+            scoring.best_response(&game, index("Rock"), Outcome::Win)
+        );
+    }
+
+    #[test]
+    fn test_cyclic_game() {
+        assert!(CyclicGame::numbered(4).is_err());
+        for n in [1, 3, 7, 9] {
+            let game = CyclicGame::numbered(n).unwrap();
+            for other in 0..n {
+                // Every move wins against, draws with and loses to the same number of moves.
+                assert_eq!(1, game.responses(other, Outcome::Draw).count());
+                assert_eq!(n / 2, game.responses(other, Outcome::Win).count());
+                assert_eq!(n / 2, game.responses(other, Outcome::Lose).count());
+                for mine in 0..n {
+                    let reverse = match game.outcome(mine, other) {
+                        Outcome::Lose => Outcome::Win,
+                        Outcome::Draw => Outcome::Draw,
+                        Outcome::Win => Outcome::Lose,
+                    };
+                    assert_eq!(reverse, game.outcome(other, mine));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_move_game() {
+        let game = CyclicGame::numbered(1).unwrap();
+        let scoring = Scoring::standard(1);
+        assert_eq!(Some(0), scoring.best_response(&game, 0, Outcome::Draw));
+        assert_eq!(None, scoring.best_response(&game, 0, Outcome::Win));
+        assert_eq!(None, scoring.best_response(&game, 0, Outcome::Lose));
+        assert_eq!(Ok(()), table_main("1"));
+    }
+
+    #[test]
+    fn test_scoring() {
+        assert_eq!(Scoring::standard(3), Scoring::RPS);
+        let game = CyclicGame::RPS;
+        let scoring = Scoring {
+            move_points: Cow::Borrowed(&[10, 20, 30]),
+            lose: -1,
+            draw: 0,
+            win: 1,
+        };
+        // Paper beats Rock.
+        assert_eq!(21, scoring.score(&game, 0, 1));
+        assert_eq!(9, scoring.score(&game, 1, 0));
+        assert_eq!(30, scoring.score(&game, 2, 2));
+    }
+
+    #[test]
+    fn test_column_two() {
+        use RpsMove::*;
+        assert_eq!(Ok(ColumnTwo::Moves([Scissors, Rock, Paper])), "SRP".parse());
+        assert_eq!(
+            Ok(ColumnTwo::Outcomes([
+                Outcome::Win,
+                Outcome::Lose,
+                Outcome::Draw
+            ])),
+            "WLD".parse()
+        );
+        assert!("RPP".parse::<ColumnTwo>().is_err());
+        assert!("RPSL".parse::<ColumnTwo>().is_err());
+        assert!("RLW".parse::<ColumnTwo>().is_err());
+    }
+
+    #[test]
+    fn test_guide_rules() {
+        assert_eq!(Ok(part1(INPUT)), GuideRules::part1().score(INPUT));
+        assert_eq!(Ok(part2(INPUT)), GuideRules::part2().score(INPUT));
+
+        let rules = GuideRules {
+            column_two: "LDW".parse().unwrap(),
+            scoring: Scoring {
+                move_points: Cow::Borrowed(&[0, 0, 0]),
+                lose: 0,
+                draw: 1,
+                win: 10,
+            },
+        };
+        assert_eq!(Ok(1 + 10), rules.score(INPUT));
+
+        assert!(GuideRules::part1().score("A Q\n").is_err());
+        assert_eq!(
+            Err("Invalid opponent move \"X\"".to_string()),
+            GuideRules::part1().score("X Y\n")
+        );
+        assert_eq!(Ok(RpsMove::Paper), "B".parse());
+        assert!("Y".parse::<RpsMove>().is_err());
+        assert!(GuideRules::part1().score("AY\n").is_err());
+    }
+
+    // Command line arguments as `main` passes them to the subcommands.
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_guide_main() {
+        assert_eq!(Ok(15), guide_main(INPUT, &args(&["RPS"])));
+        assert_eq!(Ok(12), guide_main(INPUT, &args(&["LDW"])));
+        assert_eq!(
+            Ok(2 + 1 + 3),
+            guide_main(INPUT, &args(&["RPS", "--outcome-points", "0,0,0"]))
+        );
+        assert!(guide_main(INPUT, &args(&["RPS", "--move-points", "1,2"])).is_err());
+        assert_eq!(
+            Err("Unknown guide argument \"--points\"".to_string()),
+            guide_main(INPUT, &args(&["RPS", "--points", "bad"]))
+        );
+    }
+
+    #[test]
+    fn test_permutation_scores() {
+        use RpsMove::*;
+        let scores = permutation_scores(INPUT).unwrap();
+        assert_eq!(6, scores.len());
+        assert_eq!(([Rock, Paper, Scissors], 15), scores[0]);
+        // Mapping X/Y/Z to Paper/Rock/Scissors draws every round.
+        let (_, score) = scores
+            .iter()
+            .find(|(moves, _)| *moves == [Paper, Rock, Scissors])
+            .unwrap();
+        assert_eq!((1 + 3) + (2 + 3) + (3 + 3), *score);
+    }
+
+    #[test]
+    fn test_split_mix() {
+        let mut a = SplitMix64::new(7);
+        let mut b = SplitMix64::new(7);
+        let a: Vec<_> = (0..10).map(|_| a.below(3)).collect();
+        let b: Vec<_> = (0..10).map(|_| b.below(3)).collect();
+        assert_eq!(a, b);
+        assert!(a.iter().all(|i| *i < 3));
+        assert_ne!(SplitMix64::new(1).next_u64(), SplitMix64::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_beats() {
+        use RpsMove::*;
+        assert_eq!(Paper, beats(Rock));
+        assert_eq!(Scissors, beats(Paper));
+        assert_eq!(Rock, beats(Scissors));
+    }
+
+    #[test]
+    fn test_play_match() {
+        let rocks = FixedSequence {
+            name: "rocks".to_string(),
+            moves: vec![RpsMove::Rock],
+        };
+        let mut rng = SplitMix64::new(0);
+        let (beat_last, rocks) = play_match(&BeatLast, &rocks, 10, &mut rng);
+        // Only the first round, played blind, can fail to win.
+        assert!(beat_last.wins >= 9);
+        assert_eq!(10, beat_last.wins + beat_last.draws + beat_last.losses);
+        assert_eq!(beat_last.wins, rocks.losses);
+        assert_eq!(beat_last.losses, rocks.wins);
+        assert_eq!(beat_last.draws, rocks.draws);
+        assert!(rocks.points >= 10);
+
+        let (mut a, mut b) = (Record::default(), Record::default());
+        a.add(RpsMove::Rock, RpsMove::Paper);
+        b.add(RpsMove::Paper, RpsMove::Rock);
+        assert_eq!(1, a.wins);
+        assert_eq!(1, b.losses);
+        assert_eq!(points(RpsMove::Rock, RpsMove::Paper), a.points);
+    }
+
+    #[test]
+    fn test_beat_most_frequent() {
+        use RpsMove::*;
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(
+            Rock,
+            BeatMostFrequent.next_move(&[Scissors, Rock, Scissors], &mut rng)
+        );
+        // Ties go to the first move in ALL.
+        assert_eq!(Paper, BeatMostFrequent.next_move(&[Paper, Rock], &mut rng));
+    }
+
+    #[test]
+    fn test_tournament() {
+        let strategies = guide_strategies(INPUT).unwrap();
+        let records = tournament(&strategies, 30, 42);
+        assert_eq!(records, tournament(&strategies, 30, 42));
+        for record in &records {
+            // Each strategy plays every other strategy once.
+            assert_eq!(
+                30 * (strategies.len() as u32 - 1),
+                record.wins + record.draws + record.losses
+            );
+        }
+        assert!(guide_strategies("").is_err());
+        assert!(guide_strategies("A Q\n").is_err());
+        assert_eq!(
+            Err("The guide is empty".to_string()),
+            tournament_main("", &args(&["--rounds", "5"]))
+        );
+
+        let wins: u32 = records.iter().map(|r| r.wins).sum();
+        let losses: u32 = records.iter().map(|r| r.losses).sum();
+        assert_eq!(wins, losses);
+
+        // The guide's part1 moves against the opponent column replay the guide exactly.
+        let mut rng = SplitMix64::new(0);
+        let (_, part1_record) = play_match(&*strategies[0], &*strategies[1], 3, &mut rng);
+        assert_eq!(part1(INPUT), part1_record.points);
+    }
+
+    // Predict the move after `history` with a fresh model.
+    fn predict_next(history: &[RpsMove], order: usize) -> Option<RpsMove> {
+        let mut model = NgramModel::new(order);
+        history.iter().for_each(|m| model.observe(*m));
+        model.predict()
+    }
+
+    #[test]
+    fn test_predict_next() {
+        use RpsMove::*;
+        assert_eq!(None, predict_next(&[], 2));
+        // Only frequency to go on.
+        assert_eq!(Some(Paper), predict_next(&[Paper], 2));
+        // Rock has always been followed by paper.
+        assert_eq!(
+            Some(Paper),
+            predict_next(&[Rock, Paper, Scissors, Rock, Paper, Scissors, Rock], 1)
+        );
+        // An order 2 context breaks the tie that order 1 can't.
+        let history = [Rock, Rock, Paper, Scissors, Rock, Scissors, Scissors, Rock];
+        assert_eq!(Some(Rock), predict_next(&history, 1));
+        assert_eq!(Some(Scissors), predict_next(&history, 2));
+        // Unseen order 3 context falls back to order 2.
+        assert_eq!(Some(Scissors), predict_next(&history, 3));
+    }
+
+    #[test]
+    fn test_markov_predictor() {
+        use RpsMove::*;
+        let mut rng = SplitMix64::new(0);
+        let cycle = [Rock, Paper, Scissors, Rock, Paper];
+        assert_eq!(
+            Rock,
+            MarkovPredictor::new(1).next_move(&cycle, &mut rng),
+            "expects scissors next"
+        );
+        // A new match starts the model over instead of reusing the last opponent's moves.
+        let markov = MarkovPredictor::new(1);
+        assert_eq!(Rock, markov.next_move(&cycle, &mut rng));
+        assert_eq!(Paper, markov.next_move(&[Rock, Rock, Rock], &mut rng));
+        assert_eq!("markov-3", MarkovPredictor::new(3).name());
+    }
+
+    #[test]
+    fn test_prediction_report() {
+        let report = prediction_report(INPUT, 2).unwrap();
+        assert_eq!(3, report.rounds);
+        assert_eq!(15, report.guide_part1);
+        assert_eq!(12, report.guide_part2);
+        // Opens with a rock draw, then predicts rock from frequency alone twice: paper draws with
+        // paper and loses to scissors.
+        assert_eq!(0, report.correct);
+        assert_eq!(
+            Record {
+                wins: 0,
+                draws: 2,
+                losses: 1,
+                points: (1 + 3) + (2 + 3) + 2,
+            },
+            report.predicted
+        );
+
+        let repeating = "A Y\nB X\nC Z\n".repeat(10);
+        let report = prediction_report(&repeating, 2).unwrap();
+        assert!(report.correct >= report.rounds - 3);
+        assert!(report.predicted.points > report.guide_part1);
+
+        assert_eq!(
+            Err("Invalid opponent move \"D\"".to_string()),
+            prediction_report("A Y\nD X\n", 2)
+        );
+    }
 }
@@ -1,87 +1,213 @@
-#![feature(iter_array_chunks)]
+use std::fmt;
 
-fn letter_to_index(c: char) -> u64 {
-    // [0-51]
-    match c {
-        'a'..='z' => (c as u64) - ('a' as u64),
-        'A'..='Z' => (c as u64) - ('A' as u64) + 26,
-        _ => todo!(),
+use aoc2022::item_set::{ItemError, ItemSet};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+enum RucksackError {
+    #[error(transparent)]
+    Item(#[from] ItemError),
+    #[error("{lines} rucksacks cannot be split into groups of {group_size}")]
+    UnevenGroups { lines: usize, group_size: usize },
+    #[error("line {line}: {len} items cannot be split into {compartments} equal compartments")]
+    UnevenCompartments {
+        line: usize,
+        len: usize,
+        compartments: usize,
+    },
+    #[error("line {line}: expected exactly one shared item, found {shared:?}")]
+    NotOneShared { line: usize, shared: String },
+}
+
+// The items shared by every part of a rucksack (its compartments) or of a group (its rucksacks),
+// and the items found in at least two of the parts.
+// `line` is the 1-based input line of the rucksack, or of the first rucksack in the group.
+#[derive(Debug, Clone, PartialEq)]
+struct SharedItems<'a> {
+    line: usize,
+    parts: Vec<&'a str>,
+    shared: ItemSet,
+    in_two_or_more: ItemSet,
+}
+
+impl SharedItems<'_> {
+    // Priority of the shared item. Only defined when exactly one item is shared.
+    pub fn priority(&self) -> Result<u32, RucksackError> {
+        match self.shared.len() {
+            1 => Ok(self.shared.priorities().next().unwrap()),
+            _ => Err(RucksackError::NotOneShared {
+                line: self.line,
+                shared: self.shared.to_string(),
+            }),
+        }
     }
 }
 
-// Find the repeat character between the first and second half of the line.
-fn find_repeat_char1(txt: &str) -> char {
-    // Split the input in half and find the shared element.
-    assert!(txt.is_ascii());
-    let sack_len = txt.len() / 2;
-    assert_eq!(txt.len(), sack_len * 2);
-    let chunk1 = &txt[0..sack_len];
-    let chunk2 = &txt[sack_len..];
-
-    let mut part1_entries: u64 = 0; // bitmap with 52 entries.
-    for c in chunk1.chars() {
-        let i = 1 << letter_to_index(c);
-        part1_entries |= i;
-    }
-    for c in chunk2.chars() {
-        let i = 1 << letter_to_index(c);
-        if part1_entries & i != 0 {
-            return c;
+impl fmt::Display for SharedItems<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.parts.join(" | "))?;
+        match (self.shared.is_empty(), self.priority()) {
+            (true, _) => write!(f, " share nothing"),
+            (false, Ok(priority)) => write!(f, " share {}, priority {priority}", self.shared),
+            (false, Err(_)) => write!(f, " share {}, no single priority", self.shared),
+        }?;
+        if self.parts.len() > 2 {
+            write!(f, "; {} in two or more", self.in_two_or_more)?;
         }
+        Ok(())
     }
+}
 
-    panic!("No repeat found");
+fn shared_items(line: usize, parts: Vec<&str>) -> Result<SharedItems<'_>, ItemError> {
+    let sets = parts
+        .iter()
+        .map(|part| part.parse::<ItemSet>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let shared = sets
+        .iter()
+        .copied()
+        .reduce(ItemSet::intersection)
+        .unwrap_or_default();
+    let mut seen = ItemSet::new();
+    let mut in_two_or_more = ItemSet::new();
+    for set in sets {
+        in_two_or_more = in_two_or_more.union(seen.intersection(set));
+        seen = seen.union(set);
+    }
+    Ok(SharedItems {
+        line,
+        parts,
+        shared,
+        in_two_or_more,
+    })
 }
 
-fn part1(txt: &str) -> u64 {
+// Split the rucksack on `line` into `compartments` equal parts and find the items they share.
+fn rucksack_report(
+    line: usize,
+    txt: &str,
+    compartments: usize,
+) -> Result<SharedItems<'_>, RucksackError> {
+    // Split on char boundaries so that stray non-ASCII characters are reported as bad items.
+    let boundaries: Vec<usize> = txt
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([txt.len()])
+        .collect();
+    let len = boundaries.len() - 1;
+    if compartments == 0 || !len.is_multiple_of(compartments) {
+        return Err(RucksackError::UnevenCompartments {
+            line,
+            len,
+            compartments,
+        });
+    }
+    let size = len / compartments;
+    let parts = (0..compartments)
+        .map(|i| &txt[boundaries[i * size]..boundaries[(i + 1) * size]])
+        .collect();
+    Ok(shared_items(line, parts)?)
+}
+
+fn rucksack_reports(txt: &str, compartments: usize) -> Result<Vec<SharedItems<'_>>, RucksackError> {
     txt.lines()
-        .inspect(|line| {
-            println!(
-                "{line} {} {}",
-                find_repeat_char1(line),
-                letter_to_index(find_repeat_char1(line))
-            )
-        })
-        .map(|line| {
-            let c = find_repeat_char1(line);
-            1 + letter_to_index(c)
-        })
-        .sum()
+        .enumerate()
+        .map(|(i, line)| rucksack_report(i + 1, line, compartments))
+        .collect()
 }
 
-// Find the repeat character between each entry.
-fn find_repeat_char_index(txt: &[&str]) -> u64 {
-    // Use bitmaps to find the repeat entry between lines.
-    let badge_index: u64 = txt
-        .iter()
-        .map(|line| {
-            line.chars()
-                .fold(0, |acc, c| acc | (1 << letter_to_index(c)))
-        })
-        .fold(!0, |acc, bm| acc & bm);
-    let res = badge_index.trailing_zeros() + 1;
-    assert!(
-        badge_index.is_power_of_two(), // Exactly one entry found
-        "{res}, {badge_index:b}"
-    );
-    res as u64
+// Find the badge of each consecutive group of `group_size` rucksacks.
+fn group_reports(txt: &str, group_size: usize) -> Result<Vec<SharedItems<'_>>, RucksackError> {
+    let lines: Vec<&str> = txt.lines().collect();
+    if group_size == 0 || !lines.len().is_multiple_of(group_size) {
+        return Err(RucksackError::UnevenGroups {
+            lines: lines.len(),
+            group_size,
+        });
+    }
+    lines
+        .chunks(group_size)
+        .enumerate()
+        .map(|(i, group)| Ok(shared_items(i * group_size + 1, group.to_vec())?))
+        .collect()
 }
 
-fn part2(txt: &str) -> u64 {
-    txt.lines()
-        .array_chunks::<3>()
-        .map(|lines| find_repeat_char_index(&lines))
+fn sum_priorities(reports: &[SharedItems]) -> Result<u64, RucksackError> {
+    reports
+        .iter()
+        .map(|report| Ok(report.priority()? as u64))
         .sum()
 }
 
+fn part1(txt: &str) -> Result<u64, RucksackError> {
+    sum_priorities(&rucksack_reports(txt, 2)?)
+}
+
+// Sum the badge priorities of consecutive groups of `group_size` rucksacks.
+fn badge_priorities(txt: &str, group_size: usize) -> Result<u64, RucksackError> {
+    sum_priorities(&group_reports(txt, group_size)?)
+}
+
+fn part2(txt: &str) -> Result<u64, RucksackError> {
+    badge_priorities(txt, 3)
+}
+
+// Usage: day-03 [GROUP_SIZE] [--compartments N] [--verbose]
+// Returns the verbose flag, the group size and the number of compartments.
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(bool, Option<usize>, Option<usize>), String> {
+    let number = |n: &str| n.parse().map_err(|_| format!("Invalid number {n:?}"));
+    let mut verbose = false;
+    let mut group_size = None;
+    let mut compartments = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--verbose" => verbose = true,
+            "--compartments" => {
+                let n = args.next().ok_or("Missing value for --compartments")?;
+                compartments = Some(number(&n)?);
+            }
+            n => group_size = Some(number(n)?),
+        }
+    }
+    Ok((verbose, group_size, compartments))
+}
+
 fn main() {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.extend(vec!["data", "day-03.txt"]);
     println!("{:?}", path);
     let txt = std::fs::read_to_string(path).unwrap();
 
+    let (verbose, group_size, compartments) = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("{e}");
+            return;
+        }
+    };
+
+    if verbose {
+        let reports = rucksack_reports(&txt, compartments.unwrap_or(2)).and_then(|rucksacks| {
+            let groups = group_reports(&txt, group_size.unwrap_or(3))?;
+            Ok(rucksacks.into_iter().chain(groups))
+        });
+        match reports {
+            Ok(reports) => reports.for_each(|report| println!("{report}")),
+            Err(e) => println!("{e}"),
+        }
+    }
+
     println!("{:?}", part1(&txt));
     println!("{:?}", part2(&txt));
+    if let Some(group_size) = group_size {
+        println!("{:?}", badge_priorities(&txt, group_size));
+    }
+    if let Some(compartments) = compartments {
+        let reports = rucksack_reports(&txt, compartments);
+        println!("{:?}", reports.and_then(|reports| sum_priorities(&reports)));
+    }
 }
 
 #[cfg(test)]
@@ -99,36 +225,165 @@ CrZsJsPPZsGzwwsLwLmpwMDw
 
     #[test]
     fn test_part1a() {
-        assert_eq!('p', find_repeat_char1("vJrwpWtwJgWrhcsFMMfFFhFp"));
+        let report = rucksack_report(1, "vJrwpWtwJgWrhcsFMMfFFhFp", 2).unwrap();
+        assert_eq!(vec!["vJrwpWtwJgWr", "hcsFMMfFFhFp"], report.parts);
+        assert_eq!(ItemSet::from_item('p'), Ok(report.shared));
+        assert_eq!(Ok(16), report.priority());
+        assert_eq!(
+            "line 1: vJrwpWtwJgWr | hcsFMMfFFhFp share p, priority 16",
+            report.to_string()
+        );
+        assert_eq!(
+            Err(RucksackError::Item(ItemError::NotAnItem('1'))),
+            rucksack_report(1, "ab1a", 2)
+        );
     }
 
     #[test]
     fn test_part1b() {
-        assert_eq!(157, part1(INPUT));
+        assert_eq!(Ok(157), part1(INPUT));
     }
 
     #[test]
     fn test_part2a() {
+        let reports = group_reports(INPUT, 3).unwrap();
+        assert_eq!(2, reports.len());
+        assert_eq!(1, reports[0].line);
+        assert_eq!(Ok(18), reports[0].priority());
+        assert_eq!(4, reports[1].line);
         assert_eq!(
-            18,
-            find_repeat_char_index(&[
-                "vJrwpWtwJgWrhcsFMMfFFhFp",
-                "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
-                "PmmdzqPrVvPwwTWBwg"
-            ])
-        );
-        assert_eq!(
-            52,
-            find_repeat_char_index(&[
+            vec![
                 "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
                 "ttgJtRGJQctTZtZT",
                 "CrZsJsPPZsGzwwsLwLmpwMDw"
-            ])
+            ],
+            reports[1].parts
         );
+        assert_eq!(Ok(52), reports[1].priority());
     }
 
     #[test]
     fn test_part2b() {
-        assert_eq!(70, part2(INPUT));
+        assert_eq!(Ok(70), part2(INPUT));
+        assert_eq!(
+            Err(RucksackError::Item(ItemError::NotAnItem(' '))),
+            part2("ab\na b\nb\n")
+        );
+    }
+
+    #[test]
+    fn test_badge_priorities() {
+        assert_eq!(part2(INPUT), badge_priorities(INPUT, 3));
+        // Every rucksack on its own is a group.
+        assert_eq!(Ok(3), badge_priorities("a\nb\n", 1));
+        assert_eq!(Ok(2), badge_priorities("abc\nbd\nb\nzb\n", 4));
+        assert_eq!(Ok(2 + 27), badge_priorities("ab\nbc\nAz\nAy\n", 2));
+        assert_eq!(
+            Err(RucksackError::UnevenGroups {
+                lines: 6,
+                group_size: 4
+            }),
+            badge_priorities(INPUT, 4)
+        );
+        assert_eq!(
+            Err(RucksackError::UnevenGroups {
+                lines: 6,
+                group_size: 0
+            }),
+            badge_priorities(INPUT, 0)
+        );
+    }
+
+    #[test]
+    fn test_not_one_shared() {
+        let report = rucksack_report(3, "abcd", 2).unwrap();
+        assert!(report.shared.is_empty());
+        assert_eq!(
+            Err(RucksackError::NotOneShared {
+                line: 3,
+                shared: "".to_string()
+            }),
+            report.priority()
+        );
+        assert_eq!("line 3: ab | cd share nothing", report.to_string());
+
+        let reports = group_reports("abc\nabd\nxy\nyz\n", 2).unwrap();
+        assert_eq!(
+            "line 1: abc | abd share ab, no single priority",
+            reports[0].to_string()
+        );
+        assert_eq!(
+            Err(RucksackError::NotOneShared {
+                line: 1,
+                shared: "ab".to_string()
+            }),
+            badge_priorities("abc\nabd\nxy\nyz\n", 2)
+        );
+        assert_eq!(
+            Err(RucksackError::NotOneShared {
+                line: 2,
+                shared: "".to_string()
+            }),
+            part1("abca\nabcd\n")
+        );
+    }
+
+    #[test]
+    fn test_compartments() {
+        let report = rucksack_report(1, "abcbcdbce", 3).unwrap();
+        assert_eq!(vec!["abc", "bcd", "bce"], report.parts);
+        assert_eq!("bc", report.shared.to_string());
+        assert_eq!("bc", report.in_two_or_more.to_string());
+
+        let report = rucksack_report(2, "abXcbYdaZ", 3).unwrap();
+        assert!(report.shared.is_empty());
+        assert_eq!("ab", report.in_two_or_more.to_string());
+        assert_eq!(
+            "line 2: abX | cbY | daZ share nothing; ab in two or more",
+            report.to_string()
+        );
+
+        // Odd length rucksacks split fine into an odd number of compartments.
+        let reports = rucksack_reports("aaa\nBcBdBe\n", 3).unwrap();
+        assert_eq!(Ok(1 + 28), sum_priorities(&reports));
+        assert_eq!(
+            Err(RucksackError::UnevenCompartments {
+                line: 2,
+                len: 5,
+                compartments: 2
+            }),
+            rucksack_reports("abab\nabcab\n", 2)
+        );
+        assert_eq!(
+            Err(RucksackError::UnevenCompartments {
+                line: 1,
+                len: 4,
+                compartments: 0
+            }),
+            rucksack_reports("abab\n", 0)
+        );
+        assert_eq!(
+            Err(RucksackError::Item(ItemError::NotAnItem('é'))),
+            rucksack_reports("aébé\n", 2)
+        );
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(Ok((false, None, None)), parse(&[]));
+        assert_eq!(
+            Ok((true, Some(4), Some(3))),
+            parse(&["4", "--verbose", "--compartments", "3"])
+        );
+        assert_eq!(Err("Invalid number \"x\"".to_string()), parse(&["x"]));
+        assert_eq!(
+            Err("Invalid number \"-1\"".to_string()),
+            parse(&["--compartments", "-1"])
+        );
+        assert_eq!(
+            Err("Missing value for --compartments".to_string()),
+            parse(&["--compartments"])
+        );
     }
 }
@@ -23,7 +23,7 @@ fn merged_ranges_inner(i1: MyRange, i2: MyRange) -> (MyRange, Option<MyRange>) {
     }
 
     let start = *i1.end() + 1;
-    return (i1, Some(start..=*i2.end()));
+    (i1, Some(start..=*i2.end()))
 }
 
 // Merge 2 ranges together into disjoint ranges. This is done by removing the overlap, neither range
@@ -32,9 +32,9 @@ fn merged_ranges_inner(i1: MyRange, i2: MyRange) -> (MyRange, Option<MyRange>) {
 // Returns None if one of the ranges is entirely included in another.
 fn merge_ranges(i1: MyRange, i2: MyRange) -> (MyRange, Option<MyRange>) {
     if i1.start() <= i2.start() {
-        return merged_ranges_inner(i1, i2);
+        merged_ranges_inner(i1, i2)
     } else {
-        return merged_ranges_inner(i2, i1);
+        merged_ranges_inner(i2, i1)
     }
 }
 
@@ -76,7 +76,7 @@ fn part2(input: &str) -> i32 {
     input
         .lines()
         .enumerate()
-        .filter(|(i, line)| {
+        .filter(|(_, line)| {
             let (remainder, (r1, r2)) = parse_line_raw(line).unwrap();
             assert_eq!("", remainder);
             let (merged1, merged2) = merge_ranges(r1.clone(), r2.clone());
@@ -1,4 +1,10 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::num::NonZeroUsize;
 use std::str::Lines;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take};
@@ -6,6 +12,7 @@ use nom::character::complete::{self, char};
 use nom::multi::separated_list1;
 use nom::sequence::delimited;
 use nom::IResult;
+use thiserror::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct Instruction {
@@ -24,10 +31,51 @@ impl Instruction {
     }
 }
 
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.amount, self.from, self.to)
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+enum CraneError {
+    #[error("line {line}: cannot parse instruction {text:?}")]
+    Parse { line: usize, text: String },
+    #[error("line {line}: `{instruction}` refers to stack {stack}, but stacks are 1-{n_stacks}")]
+    NoSuchStack {
+        line: usize,
+        instruction: Instruction,
+        stack: usize,
+        n_stacks: usize,
+    },
+    #[error("line {line}: `{instruction}` but stack {} only has {available} crates", instruction.from)]
+    NotEnoughCrates {
+        line: usize,
+        instruction: Instruction,
+        available: usize,
+    },
+    #[error("stack {stack} is empty")]
+    EmptyStack { stack: usize },
+    #[error("line {line}: expected a blank line after the crate drawing")]
+    MissingBlankLine { line: usize },
+    #[error("no move {n}: only {total} moves recorded")]
+    NoSuchMove { n: usize, total: usize },
+}
+
+type Arrangement = Vec<Vec<char>>;
+
+// What to report for a stack that has no crates when reading off the top of each stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EmptyStackPolicy {
+    Skip,
+    Fill(char),
+    Error,
+}
+
 // Move `amount` crates from stack `from` to stack `to`.
 // If `reverse` is true, treats the process like popping and pushing from a stack. If false, the
 // order of the boxes is maintained during the move.
-fn cranelift(boxes: &mut Vec<Vec<char>>, instruction: Instruction, reverse: bool) {
+fn cranelift(boxes: &mut [Vec<char>], instruction: Instruction, reverse: bool) {
     let Instruction { amount, from, to } = instruction;
     if from == to {
         return;
@@ -52,11 +100,168 @@ fn cranelift(boxes: &mut Vec<Vec<char>>, instruction: Instruction, reverse: bool
     }
 }
 
+// Same as `cranelift`, but checks the instruction with `check_instruction` before touching
+// anything. `line` is the input line of the instruction, used for error reporting.
+fn checked_cranelift(
+    boxes: &mut [Vec<char>],
+    instruction: Instruction,
+    reverse: bool,
+    line: usize,
+) -> Result<(), CraneError> {
+    check_instruction(boxes, instruction, line)?;
+    cranelift(boxes, instruction, reverse);
+    Ok(())
+}
+
+// Check that both stacks exist and that `from` has enough crates.
+fn check_instruction(
+    boxes: &[Vec<char>],
+    instruction: Instruction,
+    line: usize,
+) -> Result<(), CraneError> {
+    let Instruction { amount, from, to } = instruction;
+    for stack in [from, to] {
+        if stack == 0 || stack > boxes.len() {
+            return Err(CraneError::NoSuchStack {
+                line,
+                instruction,
+                stack,
+                n_stacks: boxes.len(),
+            });
+        }
+    }
+    let available = boxes[from - 1].len();
+    if available < amount {
+        return Err(CraneError::NotEnoughCrates {
+            line,
+            instruction,
+            available,
+        });
+    }
+    Ok(())
+}
+
+// The equipment moving crates between stacks. Instructions handed to a crane have already been
+// checked with `check_instruction`.
+trait CraneModel {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction);
+}
+
+impl<C: CraneModel + ?Sized> CraneModel for &mut C {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        (**self).lift(boxes, instruction)
+    }
+}
+
+impl<C: CraneModel + ?Sized> CraneModel for Box<C> {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        (**self).lift(boxes, instruction)
+    }
+}
+
+// Moves crates one at a time, reversing their order.
+struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        cranelift(boxes, instruction, true);
+    }
+}
+
+// Moves all the crates at once, keeping their order.
+struct CrateMover9001;
+
+impl CraneModel for CrateMover9001 {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        cranelift(boxes, instruction, false);
+    }
+}
+
+// Lifts the whole block at once, but rotates it while carrying it: the bottom `by` crates of the
+// block end up on top.
+struct RotatingCrane {
+    by: usize,
+}
+
+impl CraneModel for RotatingCrane {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        let Instruction { amount, to, .. } = instruction;
+        cranelift(boxes, instruction, false);
+        if amount > 0 {
+            let dst = &mut boxes[to - 1];
+            let start = dst.len() - amount;
+            dst[start..].rotate_left(self.by % amount);
+        }
+    }
+}
+
+// Can carry at most `capacity` crates, so larger moves are split into several trips of `inner`.
+struct CapacityLimited<C> {
+    capacity: NonZeroUsize,
+    inner: C,
+}
+
+impl<C: CraneModel> CraneModel for CapacityLimited<C> {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        let mut remaining = instruction.amount;
+        while remaining > 0 {
+            let amount = remaining.min(self.capacity.get());
+            self.inner.lift(
+                boxes,
+                Instruction {
+                    amount,
+                    ..instruction
+                },
+            );
+            remaining -= amount;
+        }
+    }
+}
+
+// Charges `per_move` for every trip of `inner` plus `per_crate` for every crate it carries.
+struct Costed<C> {
+    inner: C,
+    per_move: u64,
+    per_crate: u64,
+    moves: u64,
+    total: u64,
+}
+
+impl<C> Costed<C> {
+    pub fn new(inner: C, per_move: u64, per_crate: u64) -> Self {
+        Costed {
+            inner,
+            per_move,
+            per_crate,
+            moves: 0,
+            total: 0,
+        }
+    }
+}
+
+impl<C: CraneModel> CraneModel for Costed<C> {
+    fn lift(&mut self, boxes: &mut [Vec<char>], instruction: Instruction) {
+        self.inner.lift(boxes, instruction);
+        self.moves += 1;
+        self.total += self.per_move + self.per_crate * instruction.amount as u64;
+    }
+}
+
+// Pick a crane by name: "9000", "9001" or "rotate=K".
+fn parse_crane_model(name: &str) -> Result<Box<dyn CraneModel>, String> {
+    match name.split_once('=') {
+        None if name == "9000" => Ok(Box::new(CrateMover9000)),
+        None if name == "9001" => Ok(Box::new(CrateMover9001)),
+        Some(("rotate", by)) => match by.parse() {
+            Ok(by) => Ok(Box::new(RotatingCrane { by })),
+            Err(_) => Err(format!("Invalid rotation {by:?}")),
+        },
+        _ => Err(format!("Unknown crane model {name:?}")),
+    }
+}
+
 fn parse_crate(input: &str) -> IResult<&str, Option<char>> {
-    let mut parser = alt((
-        tag("   "),
-        delimited(char('['), take(1 as usize), char(']')),
-    ));
+    let mut parser = alt((tag("   "), delimited(char('['), take(1usize), char(']'))));
     let (remainder, res) = parser(input)?;
     let out = match res {
         "   " => None,
@@ -75,23 +280,33 @@ fn parse_crate_row(line: &str) -> IResult<&str, Vec<Option<char>>> {
 // This will consume the line with stack numbers from `lines`.
 fn parse_crate_stack(lines: &mut Lines) -> Vec<Vec<char>> {
     let mut crate_rows = Vec::new();
-    while let Some(line) = lines.next() {
+    let mut footer = "";
+    for line in lines.by_ref() {
         match parse_crate_row(line) {
             Ok((_txt, row)) => crate_rows.push(row),
-            Err(_) => break, // Finished parsing crates.
+            Err(_) => {
+                // Finished parsing crates, this is the row of stack numbers.
+                footer = line;
+                break;
+            }
         }
     }
 
-    // Convert the row based crates into column based crates.
-    let n_cols = crate_rows.iter().map(|row| row.len()).max().unwrap();
-    let mut crate_stacks: Vec<_> = (0..n_cols).into_iter().map(|_| Vec::new()).collect();
+    // Convert the row based crates into column based crates. The row of stack numbers also counts
+    // the stacks that are empty.
+    let n_cols = crate_rows
+        .iter()
+        .map(|row| row.len())
+        .chain([footer.split_whitespace().count()])
+        .max()
+        .unwrap_or(0);
+    let mut crate_stacks: Vec<_> = (0..n_cols).map(|_| Vec::new()).collect();
     // Reverse the rows since parsing is done from top to bottom, but the stack of crates should go
     // from bottom to top.
     for row in crate_rows.iter().rev() {
         for (i, entry) in row.iter().enumerate() {
-            match entry {
-                Some(c) => crate_stacks[i].push(c.clone()),
-                None => (),
+            if let Some(c) = entry {
+                crate_stacks[i].push(*c);
             }
         }
     }
@@ -99,6 +314,29 @@ fn parse_crate_stack(lines: &mut Lines) -> Vec<Vec<char>> {
     crate_stacks
 }
 
+// Draw the crate stacks in the same format `parse_crate_stack` reads, including the row of stack
+// numbers. Every row is padded to the full width so that empty stacks keep their column.
+fn render_crate_stack(boxes: &[Vec<char>]) -> String {
+    let height = boxes.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut lines = Vec::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = boxes
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(c) => format!("[{c}]"),
+                None => "   ".to_string(),
+            })
+            .collect();
+        lines.push(row.join(" "));
+    }
+    let footer: Vec<String> = (1..=boxes.len()).map(|i| format!("{i:^3}")).collect();
+    lines.push(footer.join(" ").trim_end().to_string());
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
 fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
     let (txt, _) = tag("move ")(input)?;
     let (txt, amount) = complete::u32(txt)?;
@@ -109,6 +347,8 @@ fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
     Ok((txt, Instruction::new(amount, from, to)))
 }
 
+// Only the tests read instructions without line-numbered errors; see `parse_input_checked`.
+#[cfg(test)]
 fn parse_instructions(lines: &mut Lines) -> Vec<Instruction> {
     let mut instructions = Vec::new();
     for line in lines {
@@ -118,6 +358,99 @@ fn parse_instructions(lines: &mut Lines) -> Vec<Instruction> {
     instructions
 }
 
+// Parse the crate stacks and the instructions, pairing each instruction with its input line.
+fn parse_input_checked(
+    input: &str,
+) -> Result<(Arrangement, Vec<(usize, Instruction)>), CraneError> {
+    let mut lines = input.lines();
+    let boxes = parse_crate_stack(&mut lines);
+
+    // Line numbers are 1-based and count the crate drawing and the blank line.
+    let blank_line = input.lines().count() - lines.clone().count() + 1;
+    if lines.next() != Some("") {
+        return Err(CraneError::MissingBlankLine { line: blank_line });
+    }
+    let first_line = blank_line + 1;
+    let mut instructions = Vec::new();
+    for (i, text) in lines.enumerate() {
+        let line = first_line + i;
+        match parse_instruction(text) {
+            Ok(("", instruction)) => instructions.push((line, instruction)),
+            _ => {
+                return Err(CraneError::Parse {
+                    line,
+                    text: text.to_string(),
+                })
+            }
+        }
+    }
+    Ok((boxes, instructions))
+}
+
+// Run all the instructions with the given crane, checking each one first.
+fn perform_update_with(
+    input: &str,
+    crane: &mut impl CraneModel,
+) -> Result<Arrangement, CraneError> {
+    let (mut boxes, instructions) = parse_input_checked(input)?;
+    for (line, instruction) in instructions {
+        check_instruction(&boxes, instruction, line)?;
+        crane.lift(&mut boxes, instruction);
+    }
+    Ok(boxes)
+}
+
+// Usage: day-05 crane MODEL [--capacity N] [--cost PER_MOVE PER_CRATE]
+// where MODEL is one of the names accepted by `parse_crane_model`.
+fn crane_main(txt: &str, args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    let model = args.next().ok_or("Missing crane model")?;
+    let mut crane = Costed::new(parse_crane_model(model)?, 1, 0);
+    let mut capacity = None;
+    while let Some(arg) = args.next() {
+        let mut number = || -> Result<u64, String> {
+            let value = args.next().ok_or(format!("{arg} needs a value"))?;
+            value
+                .parse()
+                .map_err(|_| format!("Invalid number {value:?}"))
+        };
+        match arg.as_str() {
+            "--capacity" => {
+                capacity = Some(
+                    NonZeroUsize::new(number()? as usize).ok_or("--capacity must be at least 1")?,
+                )
+            }
+            "--cost" => {
+                crane.per_move = number()?;
+                crane.per_crate = number()?;
+            }
+            other => return Err(format!("Unknown crane argument {other:?}")),
+        }
+    }
+
+    let boxes = match capacity {
+        Some(capacity) => perform_update_with(
+            txt,
+            &mut CapacityLimited {
+                capacity,
+                inner: &mut crane,
+            },
+        ),
+        None => perform_update_with(txt, &mut crane),
+    }
+    .map_err(|e| e.to_string())?;
+
+    println!("{}", render_crate_stack(&boxes));
+    println!(
+        "{:?}",
+        top_crates(&boxes, EmptyStackPolicy::Fill(' ')).unwrap()
+    );
+    println!("{} moves costing {}", crane.moves, crane.total);
+    Ok(())
+}
+
+// Unchecked reference for `perform_update_checked`, kept for the tests.
+#[cfg(test)]
 fn perform_update(input: &str, reverse: bool) -> Vec<Vec<char>> {
     let mut lines = input.lines();
     let mut boxes = parse_crate_stack(&mut lines);
@@ -131,20 +464,555 @@ fn perform_update(input: &str, reverse: bool) -> Vec<Vec<char>> {
     boxes
 }
 
-fn part1(input: &str) -> String {
-    let boxes = perform_update(input, true);
-    boxes
-        .into_iter()
-        .map(|mut stack| stack.pop().unwrap())
+// Build one frame per step of the crane: the starting stacks, then the stacks after each
+// instruction with a heading naming the crates that were moved and where they landed.
+fn replay_frames(input: &str, reverse: bool) -> Result<Vec<String>, CraneError> {
+    let (mut boxes, instructions) = parse_input_checked(input)?;
+    let total = instructions.len();
+
+    let mut frames = vec![format!("Start\n{}", render_crate_stack(&boxes))];
+    for (i, (line, instruction)) in instructions.into_iter().enumerate() {
+        checked_cranelift(&mut boxes, instruction, reverse, line)?;
+        let Instruction { amount, from, to } = instruction;
+        let dst = &boxes[to - 1];
+        let moved: String = dst[(dst.len() - amount)..].iter().collect();
+        frames.push(format!(
+            "Step {}/{total}: move {amount} from {from} to {to} ({moved} now on top of {to})\n{}",
+            i + 1,
+            render_crate_stack(&boxes)
+        ));
+    }
+    Ok(frames)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pacing {
+    // Redraw the terminal in place, waiting this long between frames. Any line from the controls
+    // pauses (switching to `Step`) and "q" quits.
+    Delay(Duration),
+    // Redraw the terminal in place and wait for a line from the controls between frames: an empty
+    // line steps, "c" plays on with the delay and "q" quits.
+    Step,
+    // Write every frame one after the other with no terminal escape codes (e.g. to a file).
+    Dump,
+}
+
+const DEFAULT_DELAY: Duration = Duration::from_millis(500);
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+// Read control lines on their own thread so that a replay can be paused while it is playing.
+fn spawn_controls(reader: impl BufRead + Send + 'static) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in reader.lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn play(
+    frames: &[String],
+    mut pacing: Pacing,
+    out: &mut impl Write,
+    controls: &Receiver<String>,
+) -> std::io::Result<()> {
+    // The delay to go back to when continuing after a pause.
+    let delay = match pacing {
+        Pacing::Delay(delay) => delay,
+        _ => DEFAULT_DELAY,
+    };
+    'frames: for (i, frame) in frames.iter().enumerate() {
+        if pacing == Pacing::Dump {
+            writeln!(out, "{frame}")?;
+            continue;
+        }
+
+        write!(out, "{CLEAR_SCREEN}{frame}")?;
+        out.flush()?;
+        if i + 1 == frames.len() {
+            break;
+        }
+
+        // Wait on this frame until it's time for the next one. Pausing stays on this frame.
+        loop {
+            match pacing {
+                Pacing::Delay(delay) => {
+                    write!(out, "[enter] pause, [q] quit")?;
+                    out.flush()?;
+                    match controls.recv_timeout(delay) {
+                        Ok(command) if command.trim() == "q" => break 'frames,
+                        Ok(_) => pacing = Pacing::Step,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        // No more controls, so just keep playing.
+                        Err(RecvTimeoutError::Disconnected) => {
+                            std::thread::sleep(delay);
+                            break;
+                        }
+                    }
+                }
+                Pacing::Step => {
+                    write!(out, "\r[enter] step, [c] continue, [q] quit: ")?;
+                    out.flush()?;
+                    match controls.recv().as_deref().map(str::trim) {
+                        Ok("c") => {
+                            pacing = Pacing::Delay(delay);
+                            break;
+                        }
+                        Ok("q") => break 'frames,
+                        _ => break,
+                    }
+                }
+                Pacing::Dump => unreachable!(),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Usage: day-05 replay [9000|9001] [--delay MS | --step | --out PATH]
+fn replay_main(txt: &str, args: &[String]) -> Result<(), String> {
+    let mut reverse = true;
+    let mut pacing = None;
+    let mut out_path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "9000" => reverse = true,
+            "9001" => reverse = false,
+            "--step" => pacing = Some(Pacing::Step),
+            "--delay" => {
+                let ms = args.next().ok_or("--delay needs a value")?;
+                let ms = ms.parse().map_err(|_| format!("Invalid delay {ms:?}"))?;
+                pacing = Some(Pacing::Delay(Duration::from_millis(ms)));
+            }
+            "--out" => out_path = Some(args.next().ok_or("--out needs a path")?),
+            other => return Err(format!("Unknown replay argument {other:?}")),
+        }
+    }
+
+    let frames = replay_frames(txt, reverse).map_err(|e| e.to_string())?;
+    match (out_path, pacing) {
+        (Some(_), Some(_)) => {
+            Err("--out writes every frame at once and can't be paced with --step or --delay".into())
+        }
+        (Some(path), None) => {
+            let file = std::fs::File::create(path).map_err(|e| format!("{path}: {e}"))?;
+            let (_, no_controls) = mpsc::channel();
+            play(
+                &frames,
+                Pacing::Dump,
+                &mut std::io::BufWriter::new(file),
+                &no_controls,
+            )
+            .map_err(|e| format!("{path}: {e}"))
+        }
+        (None, pacing) => {
+            let controls = spawn_controls(std::io::BufReader::new(std::io::stdin()));
+            play(
+                &frames,
+                pacing.unwrap_or(Pacing::Delay(DEFAULT_DELAY)),
+                &mut std::io::stdout(),
+                &controls,
+            )
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Run every instruction on the starting stacks, reporting malformed or impossible instructions
+// instead of panicking.
+fn perform_update_checked(input: &str, reverse: bool) -> Result<Vec<Vec<char>>, CraneError> {
+    CrateYard::from_input(input, reverse).map(|yard| yard.boxes)
+}
+
+// Read the top crate of each stack.
+fn top_crates(boxes: &[Vec<char>], empty: EmptyStackPolicy) -> Result<String, CraneError> {
+    let mut out = String::new();
+    for (i, stack) in boxes.iter().enumerate() {
+        match (stack.last(), empty) {
+            (Some(c), _) => out.push(*c),
+            (None, EmptyStackPolicy::Skip) => (),
+            (None, EmptyStackPolicy::Fill(c)) => out.push(c),
+            (None, EmptyStackPolicy::Error) => return Err(CraneError::EmptyStack { stack: i + 1 }),
+        }
+    }
+    Ok(out)
+}
+
+// Crate stacks together with the history of instructions applied to them. Every crane move can be
+// undone by moving the same number of crates back with the same crane, so the yard can step back
+// and forth through the history without replaying it from the start.
+#[derive(Debug, Clone, PartialEq)]
+struct CrateYard {
+    boxes: Vec<Vec<char>>,
+    history: Vec<Instruction>,
+    // Number of instructions from `history` currently applied to `boxes`.
+    applied: usize,
+    reverse: bool,
+}
+
+impl CrateYard {
+    pub fn new(boxes: Vec<Vec<char>>, reverse: bool) -> Self {
+        CrateYard {
+            boxes,
+            history: Vec::new(),
+            applied: 0,
+            reverse,
+        }
+    }
+
+    // Parse the stacks and instructions from the puzzle input and apply all of them.
+    pub fn from_input(input: &str, reverse: bool) -> Result<Self, CraneError> {
+        let (boxes, instructions) = parse_input_checked(input)?;
+        let mut yard = CrateYard::new(boxes, reverse);
+        for (line, instruction) in instructions {
+            yard.apply(instruction, line)?;
+        }
+        Ok(yard)
+    }
+
+    pub fn boxes(&self) -> &[Vec<char>] {
+        &self.boxes
+    }
+
+    pub fn history(&self) -> &[Instruction] {
+        &self.history
+    }
+
+    // Number of moves currently applied.
+    pub fn position(&self) -> usize {
+        self.applied
+    }
+
+    // Apply a new instruction after the current position, dropping any moves that were undone.
+    // `line` is the input line of the instruction, used for error reporting.
+    pub fn apply(&mut self, instruction: Instruction, line: usize) -> Result<(), CraneError> {
+        checked_cranelift(&mut self.boxes, instruction, self.reverse, line)?;
+        self.history.truncate(self.applied);
+        self.history.push(instruction);
+        self.applied += 1;
+        Ok(())
+    }
+
+    // Take back the last applied move. Returns the move, or None if nothing is applied.
+    pub fn undo(&mut self) -> Option<Instruction> {
+        let instruction = *self.history[..self.applied].last()?;
+        let Instruction { amount, from, to } = instruction;
+        let inverse = Instruction {
+            amount,
+            from: to,
+            to: from,
+        };
+        cranelift(&mut self.boxes, inverse, self.reverse);
+        self.applied -= 1;
+        Some(instruction)
+    }
+
+    // Reapply the next undone move. Returns the move, or None if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<Instruction> {
+        let instruction = *self.history.get(self.applied)?;
+        cranelift(&mut self.boxes, instruction, self.reverse);
+        self.applied += 1;
+        Some(instruction)
+    }
+
+    // Step backward or forward through the history until `n` moves are applied. Fails without
+    // moving if fewer than `n` moves are recorded.
+    pub fn seek(&mut self, n: usize) -> Result<(), CraneError> {
+        let total = self.history.len();
+        if n > total {
+            return Err(CraneError::NoSuchMove { n, total });
+        }
+        while self.applied > n {
+            self.undo();
+        }
+        while self.applied < n {
+            self.redo();
+        }
+        Ok(())
+    }
+
+    // Find every crate labelled `label` after move `n`, as (stack, height) pairs counting from 1
+    // and from the bottom of the stack. Leaves the yard at move `n`.
+    pub fn locate(&mut self, label: char, n: usize) -> Result<Vec<(usize, usize)>, CraneError> {
+        self.seek(n)?;
+        Ok(self
+            .boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, stack)| {
+                stack
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, c)| **c == label)
+                    .map(move |(j, _)| (i + 1, j + 1))
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+enum PlanError {
+    #[error("start has {start} stacks but the target has {target}")]
+    StackCount { start: usize, target: usize },
+    #[error("start and target do not hold the same crates")]
+    DifferentCrates,
+    #[error("the target arrangement cannot be reached")]
+    Unreachable,
+    #[error("no plan found after exploring {0} arrangements")]
+    SearchLimit(usize),
+}
+
+// Number of arrangements the exhaustive search explores before falling back to `greedy_plan`.
+const PLAN_SEARCH_LIMIT: usize = 100_000;
+
+enum Search {
+    Found(Vec<Instruction>),
+    Exhausted,
+    LimitReached,
+}
+
+// Find a list of instructions that turns `start` into `target` for the given crane.
+// Small arrangements get a plan with the fewest possible moves. Larger ones get a valid plan that
+// is not necessarily the shortest, which needs at least 3 stacks to work with.
+fn plan_moves(
+    start: &[Vec<char>],
+    target: &[Vec<char>],
+    reverse: bool,
+) -> Result<Vec<Instruction>, PlanError> {
+    plan_moves_with_limit(start, target, reverse, PLAN_SEARCH_LIMIT)
+}
+
+fn plan_moves_with_limit(
+    start: &[Vec<char>],
+    target: &[Vec<char>],
+    reverse: bool,
+    limit: usize,
+) -> Result<Vec<Instruction>, PlanError> {
+    if start.len() != target.len() {
+        return Err(PlanError::StackCount {
+            start: start.len(),
+            target: target.len(),
+        });
+    }
+    let mut start_crates: Vec<char> = start.iter().flatten().copied().collect();
+    let mut target_crates: Vec<char> = target.iter().flatten().copied().collect();
+    start_crates.sort_unstable();
+    target_crates.sort_unstable();
+    if start_crates != target_crates {
+        return Err(PlanError::DifferentCrates);
+    }
+
+    match shortest_plan(start, target, reverse, limit) {
+        Search::Found(plan) => Ok(plan),
+        Search::Exhausted => Err(PlanError::Unreachable),
+        Search::LimitReached if start.len() >= 3 => Ok(greedy_plan(start, target, reverse)),
+        Search::LimitReached => Err(PlanError::SearchLimit(limit)),
+    }
+}
+
+// Breadth first search over arrangements, trying every possible move from each one.
+fn shortest_plan(start: &[Vec<char>], target: &[Vec<char>], reverse: bool, limit: usize) -> Search {
+    let mut parents: HashMap<Arrangement, Option<(Arrangement, Instruction)>> = HashMap::new();
+    parents.insert(start.to_vec(), None);
+    let mut queue = VecDeque::from([start.to_vec()]);
+
+    while let Some(boxes) = queue.pop_front() {
+        if boxes == target {
+            let mut plan = Vec::new();
+            let mut current = boxes;
+            while let Some((parent, instruction)) = parents[&current].clone() {
+                plan.push(instruction);
+                current = parent;
+            }
+            plan.reverse();
+            return Search::Found(plan);
+        }
+
+        for from in 1..=boxes.len() {
+            for to in (1..=boxes.len()).filter(|to| *to != from) {
+                for amount in 1..=boxes[from - 1].len() {
+                    let instruction = Instruction { amount, from, to };
+                    let mut next = boxes.clone();
+                    cranelift(&mut next, instruction, reverse);
+                    if parents.contains_key(&next) {
+                        continue;
+                    }
+                    if parents.len() >= limit {
+                        return Search::LimitReached;
+                    }
+                    parents.insert(next.clone(), Some((boxes.clone(), instruction)));
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    Search::Exhausted
+}
+
+// Build the target one crate at a time using single crate moves, which behave the same for both
+// cranes. Each stack keeps a bottom prefix that already matches the target; every crate above it is
+// free to be moved around. To lay the next crate on a stack, clear that stack's free crates away,
+// then dig the needed crate out of another stack, parking whatever is on top of it on a third one.
+fn greedy_plan(start: &[Vec<char>], target: &[Vec<char>], reverse: bool) -> Vec<Instruction> {
+    assert!(
+        start.len() >= 3,
+        "Need at least 3 stacks to rearrange freely"
+    );
+    let mut boxes = start.to_vec();
+    let mut plan = Vec::new();
+    let mut locked: Vec<usize> = boxes
+        .iter()
+        .zip(target)
+        .map(|(stack, goal)| stack.iter().zip(goal).take_while(|(a, b)| a == b).count())
+        .collect();
+
+    let mut lift = |boxes: &mut Vec<Vec<char>>, from: usize, to: usize| {
+        let instruction = Instruction {
+            amount: 1,
+            from: from + 1,
+            to: to + 1,
+        };
+        cranelift(boxes, instruction, reverse);
+        // Consecutive single lifts between the same stacks add up to one CrateMover 9000 move.
+        match plan.last_mut() {
+            Some(Instruction { amount, from, to })
+                if reverse && (*from, *to) == (instruction.from, instruction.to) =>
+            {
+                *amount += 1
+            }
+            _ => plan.push(instruction),
+        }
+    };
+    // Any stack other than the excluded ones can hold crates for a while.
+    let spare = |excluded: &[usize]| (0..start.len()).find(|i| !excluded.contains(i)).unwrap();
+
+    for t in 0..target.len() {
+        while locked[t] < target[t].len() {
+            while boxes[t].len() > locked[t] {
+                lift(&mut boxes, t, spare(&[t]));
+            }
+
+            let needed = target[t][locked[t]];
+            let (s, depth) = (0..boxes.len())
+                .filter(|s| *s != t)
+                .find_map(|s| {
+                    boxes[s][locked[s]..]
+                        .iter()
+                        .rposition(|c| *c == needed)
+                        .map(|i| (s, locked[s] + i))
+                })
+                .expect("Start and target hold the same crates");
+            while boxes[s].len() > depth + 1 {
+                lift(&mut boxes, s, spare(&[s, t]));
+            }
+            lift(&mut boxes, s, t);
+            locked[t] += 1;
+        }
+    }
+
+    plan
+}
+
+// Write instructions in the same format `parse_instructions` reads.
+fn render_instructions(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(|instruction| format!("{instruction}\n"))
         .collect()
 }
 
+// Pick what to report for empty stacks by name: "skip", "error" or "fill=C".
+fn parse_empty_stack_policy(name: &str) -> Result<EmptyStackPolicy, String> {
+    match (name, name.strip_prefix("fill=")) {
+        ("skip", _) => Ok(EmptyStackPolicy::Skip),
+        ("error", _) => Ok(EmptyStackPolicy::Error),
+        (_, Some(fill)) if fill.chars().count() == 1 => {
+            Ok(EmptyStackPolicy::Fill(fill.chars().next().unwrap()))
+        }
+        _ => Err(format!("Unknown empty stack policy {name:?}")),
+    }
+}
+
+// Usage: day-05 plan TARGET_PATH [9000|9001]
+// where TARGET_PATH holds the drawing of the desired arrangement.
+fn plan_main(txt: &str, args: &[String]) -> Result<String, String> {
+    let path = args.first().ok_or("Missing target path")?;
+    let target_txt = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let reverse = match args.get(1).map(String::as_str) {
+        None | Some("9000") => true,
+        Some("9001") => false,
+        Some(other) => return Err(format!("Unknown crane {other:?}")),
+    };
+    let start = parse_crate_stack(&mut txt.lines());
+    let target = parse_crate_stack(&mut target_txt.lines());
+    let plan = plan_moves(&start, &target, reverse).map_err(|e| e.to_string())?;
+    Ok(render_instructions(&plan))
+}
+
+// Usage: day-05 check [9000|9001] [--empty skip|error|fill=C]
+// Runs the instructions in checked mode and prints the top crates, or the first problem found.
+fn check_main(txt: &str, args: &[String]) -> Result<String, String> {
+    let mut reverse = true;
+    let mut empty = EmptyStackPolicy::Error;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "9000" => reverse = true,
+            "9001" => reverse = false,
+            "--empty" => {
+                empty = parse_empty_stack_policy(args.next().ok_or("--empty needs a policy")?)?
+            }
+            other => return Err(format!("Unknown check argument {other:?}")),
+        }
+    }
+
+    let boxes = perform_update_checked(txt, reverse).map_err(|e| e.to_string())?;
+    top_crates(&boxes, empty).map_err(|e| e.to_string())
+}
+
+// Usage: day-05 history [9000|9001] MOVE [CRATE]
+// Shows the stacks after move MOVE and, if given, where every crate labelled CRATE is.
+fn history_main(txt: &str, args: &[String]) -> Result<(), String> {
+    let mut reverse = true;
+    let mut args = args.iter().peekable();
+    if let Some(model) = args.next_if(|arg| *arg == "9000" || *arg == "9001") {
+        reverse = model == "9000";
+    }
+    let n: usize = match args.next().map(|n| n.parse()) {
+        Some(Ok(n)) => n,
+        _ => return Err("Missing move number".to_string()),
+    };
+    let label = args.next().and_then(|label| label.chars().next());
+
+    let mut yard = CrateYard::from_input(txt, reverse).map_err(|e| e.to_string())?;
+    let total = yard.history().len();
+    yard.seek(n).map_err(|e| e.to_string())?;
+    match yard.position() {
+        0 => println!("Start"),
+        n => println!("Move {n}/{total}: {}", yard.history()[n - 1]),
+    }
+    print!("{}", render_crate_stack(yard.boxes()));
+    if let Some(label) = label {
+        for (stack, height) in yard.locate(label, n).map_err(|e| e.to_string())? {
+            println!("{label} is on stack {stack} at height {height}");
+        }
+    }
+    Ok(())
+}
+
+fn part1(input: &str) -> String {
+    let boxes = perform_update_checked(input, true).unwrap();
+    top_crates(&boxes, EmptyStackPolicy::Error).unwrap()
+}
+
 fn part2(input: &str) -> String {
-    let boxes = perform_update(input, false);
-    boxes
-        .into_iter()
-        .map(|mut stack| stack.pop().unwrap())
-        .collect()
+    let boxes = perform_update_checked(input, false).unwrap();
+    top_crates(&boxes, EmptyStackPolicy::Error).unwrap()
 }
 
 fn main() {
@@ -153,9 +1021,45 @@ fn main() {
     println!("{:?}", path);
     let txt = std::fs::read_to_string(path).unwrap();
 
-    println!("5");
-    println!("{:?}", part1(&txt));
-    println!("{:?}", part2(&txt));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("replay") => {
+            if let Err(e) = replay_main(&txt, &args[1..]) {
+                println!("{e}");
+            }
+        }
+        Some("history") => {
+            if let Err(e) = history_main(&txt, &args[1..]) {
+                println!("{e}");
+            }
+        }
+        Some("check") => match check_main(&txt, &args[1..]) {
+            Ok(top) => println!("{top:?}"),
+            Err(e) => println!("{e}"),
+        },
+        Some("plan") => match plan_main(&txt, &args[1..]) {
+            Ok(plan) => print!("{plan}"),
+            Err(e) => println!("{e}"),
+        },
+        Some("crane") => {
+            if let Err(e) = crane_main(&txt, &args[1..]) {
+                println!("{e}");
+            }
+        }
+        _ => {
+            println!("5");
+            println!("{:?}", part1(&txt));
+            match perform_update_checked(&txt, true) {
+                Ok(boxes) => println!("{}", render_crate_stack(&boxes)),
+                Err(e) => println!("{e}"),
+            }
+            println!("{:?}", part2(&txt));
+            match perform_update_checked(&txt, false) {
+                Ok(boxes) => println!("{}", render_crate_stack(&boxes)),
+                Err(e) => println!("{e}"),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -224,10 +1128,32 @@ move 1 from 1 to 2
         assert!(lines.next().is_none()); // Row of numbers is consumed.
     }
 
+    #[test]
+    fn test_render_crate_stack() {
+        let mut lines = INPUT1.lines();
+        let crate_stack = parse_crate_stack(&mut lines);
+        assert_eq!(INPUT1, render_crate_stack(&crate_stack));
+
+        // Empty stacks keep their column, so parsing the drawing gives back the same stacks.
+        let crate_stack = vec![vec![], vec!['M', 'C', 'D'], vec!['P'], vec![]];
+        let rendered = render_crate_stack(&crate_stack);
+        assert_eq!(
+            "    [D]        \n    [C]        \n    [M] [P]    \n 1   2   3   4\n",
+            rendered
+        );
+        assert_eq!(crate_stack, parse_crate_stack(&mut rendered.lines()));
+
+        let crate_stack = vec![vec![], vec![]];
+        let rendered = render_crate_stack(&crate_stack);
+        assert_eq!(" 1   2\n", rendered);
+        assert_eq!(crate_stack, parse_crate_stack(&mut rendered.lines()));
+        assert!(parse_crate_stack(&mut "".lines()).is_empty());
+    }
+
     #[test]
     fn test_part1e() {
         let mut lines = "move 1 from 2 to 1".lines();
-        let (txt, instruction) = parse_instruction(lines.next().unwrap()).unwrap();
+        let (_txt, instruction) = parse_instruction(lines.next().unwrap()).unwrap();
         assert_eq!(Instruction::new(1, 2, 1), instruction);
     }
 
@@ -259,6 +1185,460 @@ move 1 from 1 to 2
         );
     }
 
+    #[test]
+    fn test_replay_frames() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        let frames = replay_frames(&input, true).unwrap();
+        assert_eq!(5, frames.len());
+        assert_eq!(format!("Start\n{INPUT1}"), frames[0]);
+        assert_eq!(
+            "Step 2/4: move 3 from 1 to 3 (DNZ now on top of 3)
+        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3
+",
+            frames[2]
+        );
+
+        let frames = replay_frames(&input, false).unwrap();
+        assert!(frames[2].starts_with("Step 2/4: move 3 from 1 to 3 (ZND now on top of 3)"));
+
+        let input = format!("{INPUT1}\nmove 5 from 1 to 2\n");
+        assert_eq!(
+            Err(CraneError::NotEnoughCrates {
+                line: 6,
+                instruction: Instruction::new(5, 1, 2),
+                available: 2,
+            }),
+            replay_frames(&input, true)
+        );
+        assert!(replay_main(&input, &args(&["--out", "/dev/null"]))
+            .unwrap_err()
+            .starts_with("line 6:"));
+    }
+
+    #[test]
+    fn test_play() {
+        // Queue up the given control lines, then hang up.
+        let controls = |lines: &[&str]| {
+            let (sender, receiver) = mpsc::channel();
+            for line in lines {
+                sender.send(line.to_string()).unwrap();
+            }
+            receiver
+        };
+        let input = format!("{INPUT1}\n{INPUT2}");
+        let frames = replay_frames(&input, true).unwrap();
+
+        let mut out = Vec::new();
+        play(&frames, Pacing::Dump, &mut out, &controls(&[])).unwrap();
+        assert_eq!(
+            frames.iter().map(|f| format!("{f}\n")).collect::<String>(),
+            String::from_utf8(out).unwrap()
+        );
+
+        // Step twice then quit: three frames drawn.
+        let mut out = Vec::new();
+        play(&frames, Pacing::Step, &mut out, &controls(&["", "", "q"])).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(3, out.matches(CLEAR_SCREEN).count());
+        assert!(out.contains("Step 2/4"));
+        assert!(!out.contains("Step 3/4"));
+
+        // Continue plays the remaining frames at the starting delay without further input.
+        let mut out = Vec::new();
+        let delay = Pacing::Delay(Duration::from_millis(1));
+        play(&frames, delay, &mut out, &controls(&["", "c"])).unwrap();
+        assert_eq!(
+            frames.len(),
+            String::from_utf8(out)
+                .unwrap()
+                .matches(CLEAR_SCREEN)
+                .count()
+        );
+
+        // Pausing while playing stays on the current frame until stepped.
+        let mut out = Vec::new();
+        play(&frames, delay, &mut out, &controls(&["", "", "q"])).unwrap();
+        assert_eq!(
+            2,
+            String::from_utf8(out)
+                .unwrap()
+                .matches(CLEAR_SCREEN)
+                .count()
+        );
+
+        // Continue, then pause again and go back to stepping.
+        let mut out = Vec::new();
+        let commands = controls(&["c", "", "", "q"]);
+        play(&frames, Pacing::Step, &mut out, &commands).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(3, out.matches(CLEAR_SCREEN).count());
+        assert_eq!(3, out.matches("[enter] step").count());
+    }
+
+    #[test]
+    fn test_replay_main_args() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        assert!(replay_main(&input, &args(&["--bounce"])).is_err());
+        assert!(replay_main(&input, &args(&["--delay", "soon"])).is_err());
+        assert!(replay_main(&input, &args(&["--delay"])).is_err());
+        assert!(replay_main(&input, &args(&["--step", "--out", "replay.txt"])).is_err());
+    }
+
+    #[test]
+    fn test_checked_cranelift() {
+        let mut crates = vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']];
+        assert_eq!(
+            Err(CraneError::NotEnoughCrates {
+                line: 7,
+                instruction: Instruction::new(5, 1, 2),
+                available: 2,
+            }),
+            checked_cranelift(&mut crates, Instruction::new(5, 1, 2), true, 7)
+        );
+        assert_eq!(
+            Err(CraneError::NoSuchStack {
+                line: 8,
+                instruction: Instruction::new(1, 0, 2),
+                stack: 0,
+                n_stacks: 3,
+            }),
+            checked_cranelift(&mut crates, Instruction::new(1, 0, 2), true, 8)
+        );
+        assert_eq!(
+            Err(CraneError::NoSuchStack {
+                line: 9,
+                instruction: Instruction::new(1, 1, 4),
+                stack: 4,
+                n_stacks: 3,
+            }),
+            checked_cranelift(&mut crates, Instruction::new(1, 1, 4), true, 9)
+        );
+        // Failed instructions leave the stacks untouched.
+        assert_eq!(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']], crates);
+
+        assert_eq!(
+            Ok(()),
+            checked_cranelift(&mut crates, Instruction::new(1, 2, 1), true, 10)
+        );
+        assert_eq!(vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']], crates);
+    }
+
+    #[test]
+    fn test_perform_update_checked() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        assert_eq!(
+            Ok(vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']]),
+            perform_update_checked(&input, true)
+        );
+
+        let input = format!("{INPUT1}\nmove 1 from 2 to 1\nmove 5 from 1 to 3\n");
+        let err = perform_update_checked(&input, true).unwrap_err();
+        assert_eq!(
+            "line 7: `move 5 from 1 to 3` but stack 1 only has 3 crates",
+            err.to_string()
+        );
+
+        let input = format!("{INPUT1}\nmove 1 from 2 to 1\nmove one from 1 to 3\n");
+        assert_eq!(
+            Err(CraneError::Parse {
+                line: 7,
+                text: "move one from 1 to 3".to_string()
+            }),
+            perform_update_checked(&input, true)
+        );
+
+        let input = format!("{INPUT1}move 1 from 2 to 1\n");
+        assert_eq!(
+            Err(CraneError::MissingBlankLine { line: 5 }),
+            perform_update_checked(&input, true)
+        );
+        assert_eq!(
+            Err(CraneError::MissingBlankLine { line: 5 }),
+            perform_update_checked(INPUT1, true)
+        );
+        assert_eq!(
+            Err(CraneError::MissingBlankLine { line: 1 }),
+            perform_update_checked("", true)
+        );
+    }
+
+    #[test]
+    fn test_top_crates() {
+        let crates = vec![vec!['C'], vec![], vec!['P', 'D']];
+        assert_eq!(
+            Ok("CD".to_string()),
+            top_crates(&crates, EmptyStackPolicy::Skip)
+        );
+        assert_eq!(
+            Ok("C D".to_string()),
+            top_crates(&crates, EmptyStackPolicy::Fill(' '))
+        );
+        assert_eq!(
+            Err(CraneError::EmptyStack { stack: 2 }),
+            top_crates(&crates, EmptyStackPolicy::Error)
+        );
+    }
+
+    #[test]
+    fn test_crate_yard() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        for reverse in [true, false] {
+            let mut yard = CrateYard::from_input(&input, reverse).unwrap();
+            assert_eq!(4, yard.position());
+            assert_eq!(perform_update(&input, reverse), yard.boxes());
+
+            // Every intermediate state matches a fresh replay of that many instructions.
+            for n in (0..=4).rev() {
+                yard.seek(n).unwrap();
+                let mut expected = parse_crate_stack(&mut INPUT1.lines());
+                for instruction in &yard.history()[..n] {
+                    cranelift(&mut expected, *instruction, reverse);
+                }
+                assert_eq!(expected, yard.boxes());
+            }
+
+            assert_eq!(None, yard.undo());
+            assert_eq!(Some(Instruction::new(1, 2, 1)), yard.redo());
+            assert_eq!(Some(Instruction::new(1, 2, 1)), yard.undo());
+            yard.seek(4).unwrap();
+            assert_eq!(None, yard.redo());
+            assert_eq!(Err(CraneError::NoSuchMove { n: 5, total: 4 }), yard.seek(5));
+            assert_eq!(4, yard.position());
+        }
+    }
+
+    #[test]
+    fn test_crate_yard_apply() {
+        let mut yard = CrateYard::new(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']], true);
+        yard.apply(Instruction::new(1, 2, 1), 1).unwrap();
+        yard.apply(Instruction::new(3, 1, 3), 2).unwrap();
+        yard.undo();
+
+        // A new move after an undo replaces the undone one.
+        yard.apply(Instruction::new(1, 1, 2), 3).unwrap();
+        assert_eq!(
+            &[Instruction::new(1, 2, 1), Instruction::new(1, 1, 2)],
+            yard.history()
+        );
+        assert_eq!(None, yard.redo());
+        assert_eq!(
+            Err(CraneError::NotEnoughCrates {
+                line: 4,
+                instruction: Instruction::new(4, 1, 2),
+                available: 2,
+            }),
+            yard.apply(Instruction::new(4, 1, 2), 4)
+        );
+        assert_eq!(2, yard.position());
+    }
+
+    #[test]
+    fn test_crate_yard_locate() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        let mut yard = CrateYard::from_input(&input, true).unwrap();
+        assert_eq!(Ok(vec![(2, 3)]), yard.locate('D', 0));
+        assert_eq!(Ok(vec![(1, 3)]), yard.locate('D', 1));
+        assert_eq!(Ok(vec![(3, 2)]), yard.locate('D', 2));
+        assert_eq!(2, yard.position());
+        assert_eq!(Ok(vec![(3, 4)]), yard.locate('Z', 4));
+        assert_eq!(Ok(vec![]), yard.locate('X', 3));
+        assert!(yard.locate('Z', 5).is_err());
+    }
+
+    // Command line arguments as `main` passes them to the subcommands.
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn apply_all(start: &[Vec<char>], plan: &[Instruction], reverse: bool) -> Vec<Vec<char>> {
+        let mut boxes = start.to_vec();
+        for instruction in plan {
+            checked_cranelift(&mut boxes, *instruction, reverse, 0).unwrap();
+        }
+        boxes
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let start = parse_crate_stack(&mut INPUT1.lines());
+        for reverse in [true, false] {
+            let target = perform_update(&format!("{INPUT1}\n{INPUT2}"), reverse);
+            let plan = plan_moves(&start, &target, reverse).unwrap();
+            assert!(plan.len() <= 4, "{plan:?}");
+            assert_eq!(target, apply_all(&start, &plan, reverse));
+
+            // The rendered plan parses back into the same instructions.
+            let text = render_instructions(&plan);
+            assert_eq!(plan, parse_instructions(&mut text.lines()));
+        }
+
+        assert_eq!(Ok(vec![]), plan_moves(&start, &start, true));
+        assert_eq!(
+            Ok(vec![Instruction::new(2, 1, 3)]),
+            plan_moves(
+                &start,
+                &[vec![], vec!['M', 'C', 'D'], vec!['P', 'N', 'Z']],
+                true
+            )
+        );
+        assert_eq!(
+            Ok(vec![Instruction::new(2, 1, 3)]),
+            plan_moves(
+                &start,
+                &[vec![], vec!['M', 'C', 'D'], vec!['P', 'Z', 'N']],
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_plan_moves_errors() {
+        let start = vec![vec!['A', 'B'], vec![]];
+        assert_eq!(
+            Err(PlanError::StackCount {
+                start: 2,
+                target: 1
+            }),
+            plan_moves(&start, &[vec!['A', 'B']], true)
+        );
+        assert_eq!(
+            Err(PlanError::DifferentCrates),
+            plan_moves(&start, &[vec!['A'], vec!['C']], true)
+        );
+        // Two stacks and single crate lifts can never change the order of the crates.
+        assert_eq!(
+            Err(PlanError::Unreachable),
+            plan_moves(&start, &[vec!['B', 'A'], vec![]], true)
+        );
+        // Moving both at once with the CrateMover 9001 then one back does it.
+        assert_eq!(
+            Ok(vec![Instruction::new(2, 1, 2), Instruction::new(1, 2, 1)]),
+            plan_moves(&start, &[vec!['B'], vec!['A']], false)
+        );
+        assert_eq!(
+            Err(PlanError::SearchLimit(3)),
+            plan_moves_with_limit(&start, &[vec!['B'], vec!['A']], false, 3)
+        );
+    }
+
+    #[test]
+    fn test_greedy_plan() {
+        let start = vec![
+            vec!['A', 'B', 'C', 'D'],
+            vec!['E', 'F'],
+            vec!['A', 'G', 'H'],
+            vec![],
+        ];
+        let target = vec![
+            vec!['H', 'G', 'F'],
+            vec!['A'],
+            vec!['E', 'D', 'C'],
+            vec!['B', 'A'],
+        ];
+        for reverse in [true, false] {
+            let plan = greedy_plan(&start, &target, reverse);
+            assert_eq!(target, apply_all(&start, &plan, reverse));
+
+            // Falls back to the greedy plan when the search gives up.
+            let plan = plan_moves_with_limit(&start, &target, reverse, 10).unwrap();
+            assert_eq!(target, apply_all(&start, &plan, reverse));
+        }
+    }
+
+    #[test]
+    fn test_crane_models() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        assert_eq!(
+            Ok(perform_update(&input, true)),
+            perform_update_with(&input, &mut CrateMover9000)
+        );
+        assert_eq!(
+            Ok(perform_update(&input, false)),
+            perform_update_with(&input, &mut CrateMover9001)
+        );
+
+        // Rotating by a multiple of the block size is the same as the CrateMover 9001.
+        assert_eq!(
+            Ok(perform_update(&input, false)),
+            perform_update_with(&input, &mut RotatingCrane { by: 6 })
+        );
+        let mut crates = vec![vec!['A', 'B', 'C', 'D'], vec![]];
+        RotatingCrane { by: 1 }.lift(&mut crates, Instruction::new(3, 1, 2));
+        assert_eq!(vec![vec!['A'], vec!['C', 'D', 'B']], crates);
+
+        // A CrateMover 9001 that carries two at a time moves the top pair first.
+        let mut crates = vec![vec!['A', 'B', 'C', 'D', 'E'], vec![]];
+        let mut crane = CapacityLimited {
+            capacity: NonZeroUsize::new(2).unwrap(),
+            inner: CrateMover9001,
+        };
+        crane.lift(&mut crates, Instruction::new(5, 1, 2));
+        assert_eq!(vec![vec![], vec!['D', 'E', 'B', 'C', 'A']], crates);
+
+        // A CrateMover 9000 is unaffected by the capacity, but makes more trips.
+        let mut crane = CapacityLimited {
+            capacity: NonZeroUsize::new(2).unwrap(),
+            inner: Costed::new(CrateMover9000, 10, 1),
+        };
+        assert_eq!(
+            Ok(perform_update(&input, true)),
+            perform_update_with(&input, &mut crane)
+        );
+        assert_eq!(5, crane.inner.moves);
+        assert_eq!(57, crane.inner.total);
+
+        assert_eq!(
+            Err("--capacity must be at least 1".to_string()),
+            crane_main(&input, &args(&["9000", "--capacity", "0"]))
+        );
+    }
+
+    #[test]
+    fn test_parse_crane_model() {
+        let input = format!("{INPUT1}\n{INPUT2}");
+        let mut crane = parse_crane_model("9001").unwrap();
+        assert_eq!(
+            Ok(perform_update(&input, false)),
+            perform_update_with(&input, &mut crane)
+        );
+        assert!(parse_crane_model("rotate=2").is_ok());
+        assert!(parse_crane_model("rotate=x").is_err());
+        assert!(parse_crane_model("9002").is_err());
+    }
+
+    #[test]
+    fn test_perform_update_with_checks() {
+        let input = format!("{INPUT1}\nmove 4 from 2 to 1\n");
+        assert_eq!(
+            Err(CraneError::NotEnoughCrates {
+                line: 6,
+                instruction: Instruction::new(4, 2, 1),
+                available: 3
+            }),
+            perform_update_with(&input, &mut CrateMover9000)
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_stack_policy() {
+        assert_eq!(Ok(EmptyStackPolicy::Skip), parse_empty_stack_policy("skip"));
+        assert_eq!(
+            Ok(EmptyStackPolicy::Error),
+            parse_empty_stack_policy("error")
+        );
+        assert_eq!(
+            Ok(EmptyStackPolicy::Fill('_')),
+            parse_empty_stack_policy("fill=_")
+        );
+        assert!(parse_empty_stack_policy("fill=").is_err());
+        assert!(parse_empty_stack_policy("fill=ab").is_err());
+        assert!(parse_empty_stack_policy("zero").is_err());
+    }
+
     #[test]
     fn test_part1h() {
         let input = format!("{INPUT1}\n{INPUT2}");
@@ -270,4 +1650,21 @@ move 1 from 1 to 2
         let input = format!("{INPUT1}\n{INPUT2}");
         assert_eq!("MCD", &part2(&input));
     }
+
+    #[test]
+    fn test_plan_main() {
+        assert!(plan_main(INPUT1, &[]).is_err());
+        let missing = "/nonexistent/day-05-target.txt";
+        assert!(plan_main(INPUT1, &args(&[missing]))
+            .unwrap_err()
+            .starts_with(missing));
+
+        let target = std::env::temp_dir().join(format!("day-05-plan-{}.txt", std::process::id()));
+        std::fs::write(&target, "[N] [C] [D]\n[Z] [M] [P]\n 1   2   3\n").unwrap();
+        let target = target.to_str().unwrap();
+        let plan = plan_main(INPUT1, &args(&[target, "9001"])).unwrap();
+        assert_eq!("move 1 from 2 to 3\n", plan);
+        assert!(plan_main(INPUT1, &args(&[target, "9002"])).is_err());
+        std::fs::remove_file(target).unwrap();
+    }
 }
@@ -1,47 +1,311 @@
-use ringbuf::ring_buffer::Container;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::mem::MaybeUninit;
+use std::time::Instant;
+
+use aoc2022::item_set::{ItemError, ItemSet};
 use ringbuf::{LocalRb, Rb};
 
-fn letter_to_index(c: char) -> u64 {
-    // [1-52]
-    match c {
-        'a'..='z' => (c as u64) - ('a' as u64) + 1,
-        'A'..='Z' => (c as u64) - ('A' as u64) + 27,
-        _ => todo!(),
+// Original detector: keep the last `header_size` letters as item sets and union them all together
+// on every character. Only handles ASCII letters. Kept to benchmark against `marker_end`.
+fn msg_start_ringbuf(packet: &str, header_size: usize) -> Result<Option<usize>, ItemError> {
+    let mut buf = LocalRb::new(header_size);
+    for (i, c) in packet.char_indices() {
+        buf.push_overwrite(ItemSet::from_item(c)?); // No need to pop.
+        let fold = buf.iter().fold(ItemSet::new(), |acc, set| acc.union(*set));
+
+        if fold.len() == header_size {
+            return Ok(Some(i + 1));
+        }
     }
+
+    Ok(None)
 }
 
-fn msg_start(packet: &str, header_size: usize) -> usize {
-    let mut buf = LocalRb::new(header_size);
-    for (i, c) in packet.char_indices() {
-        let bitmapped_index = 1 << letter_to_index(c);
-        buf.push_overwrite(bitmapped_index); // No need to pop.
-        let fold: u64 = buf.iter().fold(0, |acc, bitmap| acc | bitmap);
+// Incremental marker detector fed one byte at a time. Keeps a count per byte value and the number
+// of distinct bytes in the window, so each byte costs the same no matter the window size. After a
+// marker it starts over, so consecutive markers never share bytes.
+struct MarkerScanner {
+    header_size: usize,
+    window: LocalRb<u8, Vec<MaybeUninit<u8>>>,
+    counts: [usize; 256],
+    distinct: usize,
+}
 
-        if fold.count_ones() as usize == header_size {
-            return i + 1;
+impl MarkerScanner {
+    pub fn new(header_size: usize) -> Self {
+        assert!(header_size > 0);
+        MarkerScanner {
+            header_size,
+            window: LocalRb::new(header_size),
+            counts: [0; 256],
+            distinct: 0,
+        }
+    }
+
+    // Add the next byte. Returns true if it completes a marker.
+    pub fn push(&mut self, b: u8) -> bool {
+        if self.counts[b as usize] == 0 {
+            self.distinct += 1;
+        }
+        self.counts[b as usize] += 1;
+        if let Some(old) = self.window.push_overwrite(b) {
+            self.remove(old);
         }
+
+        if self.distinct == self.header_size {
+            while let Some(old) = self.window.pop() {
+                self.remove(old);
+            }
+            return true;
+        }
+        false
+    }
+
+    fn remove(&mut self, b: u8) {
+        self.counts[b as usize] -= 1;
+        if self.counts[b as usize] == 0 {
+            self.distinct -= 1;
+        }
+    }
+}
+
+// Find the end of the first window of `header_size` distinct bytes.
+fn marker_end(data: &[u8], header_size: usize) -> Option<usize> {
+    let mut scanner = MarkerScanner::new(header_size);
+    data.iter().position(|b| scanner.push(*b)).map(|i| i + 1)
+}
+
+const PACKET_HEADER_SIZE: usize = 4;
+const MESSAGE_HEADER_SIZE: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MarkerKind {
+    Packet,
+    Message,
+}
+
+// A marker found in a stream. `end` counts the bytes read up to and including the marker, which is
+// the puzzle's answer for the first one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Marker {
+    kind: MarkerKind,
+    end: usize,
+}
+
+// Reports every start-of-packet and start-of-message marker in a stream as soon as the byte that
+// completes it is read. Only one buffer of the reader is held in memory at a time.
+struct MarkerStream<R> {
+    reader: R,
+    packet: MarkerScanner,
+    message: MarkerScanner,
+    offset: usize,
+    pending: VecDeque<Marker>,
+}
+
+impl<R: BufRead> MarkerStream<R> {
+    pub fn new(reader: R) -> Self {
+        MarkerStream {
+            reader,
+            packet: MarkerScanner::new(PACKET_HEADER_SIZE),
+            message: MarkerScanner::new(MESSAGE_HEADER_SIZE),
+            offset: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for MarkerStream<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let buf = match self.reader.fill_buf() {
+                Ok([]) => return None,
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+
+            for &b in buf {
+                self.offset += 1;
+                let end = self.offset;
+                if self.packet.push(b) {
+                    let kind = MarkerKind::Packet;
+                    self.pending.push_back(Marker { kind, end });
+                }
+                if self.message.push(b) {
+                    let kind = MarkerKind::Message;
+                    self.pending.push_back(Marker { kind, end });
+                }
+            }
+            let len = buf.len();
+            self.reader.consume(len);
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+// A message payload and the offset of its first byte in the datastream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frame<'a> {
+    offset: usize,
+    payload: &'a [u8],
+}
+
+// Splits a datastream into messages. Everything up to the first start-of-packet marker is skipped,
+// then each start-of-message marker begins a message which runs until the next marker (or the end
+// of the data). Bytes between the packet marker and the first message marker are skipped too.
+struct FrameDecoder<'a> {
+    data: &'a [u8],
+    // Start of the next payload, or None once the data is used up.
+    payload_start: Option<usize>,
+}
+
+impl<'a> FrameDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let payload_start = marker_end(data, PACKET_HEADER_SIZE).and_then(|packet_end| {
+            marker_end(&data[packet_end..], MESSAGE_HEADER_SIZE).map(|end| packet_end + end)
+        });
+        FrameDecoder {
+            data,
+            payload_start,
+        }
+    }
+}
+
+impl<'a> Iterator for FrameDecoder<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.payload_start?;
+        let rest = &self.data[offset..];
+        let payload = match marker_end(rest, MESSAGE_HEADER_SIZE) {
+            Some(end) => {
+                self.payload_start = Some(offset + end);
+                &rest[..(end - MESSAGE_HEADER_SIZE)]
+            }
+            None => {
+                self.payload_start = None;
+                rest
+            }
+        };
+        Some(Frame { offset, payload })
+    }
+}
+
+// Usage: day-06 stream [PATH]
+// Reads from stdin if no path is given.
+fn stream_main(path: Option<&String>) -> io::Result<()> {
+    let reader: Box<dyn BufRead> = match path {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(io::stdin().lock()),
+    };
+    for marker in MarkerStream::new(reader) {
+        let Marker { kind, end } = marker?;
+        println!("{kind:?} {end}");
     }
+    Ok(())
+}
+
+fn msg_start(packet: &str, header_size: usize) -> usize {
+    marker_end(packet.as_bytes(), header_size).expect("MSG NEVER STARTS!")
+}
+
+// Letters that never contain a window of `header_size` distinct letters, so both detectors have to
+// scan all of it.
+fn bench_input(len: usize, header_size: usize) -> String {
+    let alphabet: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+    let n_letters = alphabet.len().min(header_size - 1);
+    let mut state: u64 = 0x2545F4914F6CDD1D;
+    (0..len)
+        .map(|_| {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            alphabet[(state % n_letters as u64) as usize]
+        })
+        .collect()
+}
 
-    panic!("MSG NEVER STARTS!");
+// Usage: day-06 frames PATH
+// Prints the offset and payload of every message frame in the file.
+fn frames_main(args: &[String]) -> Result<String, String> {
+    let path = args.first().ok_or("Missing path")?;
+    let data = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    Ok(FrameDecoder::new(&data)
+        .map(|Frame { offset, payload }| {
+            format!("{offset}: {}\n", String::from_utf8_lossy(payload))
+        })
+        .collect())
+}
+
+// Usage: day-06 bench [LEN]
+fn bench_main(args: &[String]) -> Result<(), String> {
+    let len = match args.first() {
+        Some(len) => len
+            .parse()
+            .map_err(|e| format!("Invalid length {len:?}: {e}"))?,
+        None => 1_000_000,
+    };
+    for header_size in [4, 14, 1000] {
+        let input = bench_input(len, header_size);
+
+        let start = Instant::now();
+        let ringbuf = msg_start_ringbuf(&input, header_size).unwrap();
+        let ringbuf_time = start.elapsed();
+
+        let start = Instant::now();
+        let counts = marker_end(input.as_bytes(), header_size);
+        let counts_time = start.elapsed();
+
+        assert_eq!(ringbuf, counts);
+        println!(
+            "window {header_size:>4}: ringbuf fold {ringbuf_time:>12?}, byte counts {counts_time:>12?}"
+        );
+    }
+    Ok(())
 }
 
 fn part1(packet: &str) -> usize {
-    msg_start(packet, 4)
+    msg_start(packet, PACKET_HEADER_SIZE)
 }
 
 fn part2(packet: &str) -> usize {
-    msg_start(packet, 14)
+    msg_start(packet, MESSAGE_HEADER_SIZE)
 }
 
 fn main() {
-    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.extend(vec!["data", "day-06.txt"]);
-    println!("{:?}", path);
-    let txt = std::fs::read_to_string(path).unwrap();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("bench") => {
+            if let Err(e) = bench_main(&args[1..]) {
+                println!("{e}");
+            }
+        }
+        Some("stream") => {
+            if let Err(e) = stream_main(args.get(1)) {
+                println!("{e}");
+            }
+        }
+        Some("frames") => match frames_main(&args[1..]) {
+            Ok(frames) => print!("{frames}"),
+            Err(e) => println!("{e}"),
+        },
+        _ => {
+            let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.extend(vec!["data", "day-06.txt"]);
+            println!("{:?}", path);
+            let txt = std::fs::read_to_string(path).unwrap();
 
-    println!("6");
-    println!("{:?}", part1(&txt));
-    println!("{:?}", part2(&txt));
+            println!("6");
+            println!("{:?}", part1(&txt));
+            println!("{:?}", part2(&txt));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -65,4 +329,185 @@ mod tests {
         assert_eq!(29, part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"));
         assert_eq!(26, part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"));
     }
+
+    #[test]
+    fn test_marker_end() {
+        assert_eq!(Some(7), marker_end(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4));
+        assert_eq!(Some(1), marker_end(b"a", 1));
+        assert_eq!(None, marker_end(b"abcabc", 4));
+        assert_eq!(Some(6), marker_end(&[0, 0, 255, 7, 0, 1, 1], 4));
+
+        let bytes: Vec<u8> = (0..=255).chain(0..=255).collect();
+        assert_eq!(Some(256), marker_end(&bytes, 256));
+        assert_eq!(None, marker_end(&bytes, 257));
+    }
+
+    #[test]
+    fn test_marker_scanner() {
+        // Starts over after each marker.
+        let mut scanner = MarkerScanner::new(3);
+        let found: Vec<bool> = b"abcabcaab".iter().map(|b| scanner.push(*b)).collect();
+        assert_eq!(
+            vec![false, false, true, false, false, true, false, false, false],
+            found
+        );
+    }
+
+    #[test]
+    fn test_marker_stream() {
+        let markers: Vec<Marker> = MarkerStream::new("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            Marker {
+                kind: MarkerKind::Packet,
+                end: 7
+            },
+            markers[0]
+        );
+        let messages: Vec<usize> = markers
+            .iter()
+            .filter(|m| m.kind == MarkerKind::Message)
+            .map(|m| m.end)
+            .collect();
+        assert_eq!(vec![19], messages);
+        let packets: Vec<usize> = markers
+            .iter()
+            .filter(|m| m.kind == MarkerKind::Packet)
+            .map(|m| m.end)
+            .collect();
+        assert_eq!(vec![7, 11, 15, 19, 23, 27], packets);
+
+        // The same markers come out when the reader hands over one byte at a time.
+        let input = "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes();
+        let whole: Vec<Marker> = MarkerStream::new(input).map(Result::unwrap).collect();
+        let bytewise: Vec<Marker> = MarkerStream::new(BufReader::with_capacity(1, input))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(whole, bytewise);
+        assert_eq!(10, whole[0].end);
+    }
+
+    #[test]
+    fn test_frame_decoder() {
+        let data = b"aaabcdabcdefghijklmnhello  abcdefghijklmworld";
+        let frames: Vec<Frame> = FrameDecoder::new(data).collect();
+        assert_eq!(
+            vec![
+                Frame {
+                    offset: 20,
+                    payload: b"hello "
+                },
+                Frame {
+                    offset: 40,
+                    payload: b"world"
+                },
+            ],
+            frames
+        );
+
+        assert_eq!(None, FrameDecoder::new(b"aaaa").next());
+        assert_eq!(None, FrameDecoder::new(b"abcdaaaa").next());
+    }
+
+    // Wrap payloads in markers. Each payload must not hold 14 distinct bytes in a row. Each message
+    // marker starts with the last byte of the previous payload so that no window reaching back into
+    // the payload looks like a marker.
+    fn encode_frames(payloads: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = b"abcd".to_vec();
+        let mut last = b'd';
+        for payload in payloads {
+            assert_eq!(None, marker_end(payload, MESSAGE_HEADER_SIZE));
+            out.push(last);
+            out.extend(
+                (0..=255)
+                    .filter(|b| *b != last)
+                    .take(MESSAGE_HEADER_SIZE - 1),
+            );
+            out.extend(payload);
+            last = *out.last().unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut state: u64 = 0x9E3779B97F4A7C15;
+        let mut rand = |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+
+        for _ in 0..500 {
+            // Fewer than 14 symbols, so payloads never contain a marker.
+            let alphabet: Vec<u8> = (0..=rand(12)).map(|_| rand(256) as u8).collect();
+            let payloads: Vec<Vec<u8>> = (0..rand(8))
+                .map(|_| {
+                    (0..rand(40))
+                        .map(|_| alphabet[rand(alphabet.len() as u64) as usize])
+                        .collect()
+                })
+                .collect();
+
+            let data = encode_frames(&payloads);
+            let frames: Vec<Frame> = FrameDecoder::new(&data).collect();
+            assert_eq!(payloads.len(), frames.len());
+            for (payload, frame) in payloads.iter().zip(&frames) {
+                assert_eq!(payload, frame.payload);
+                assert_eq!(
+                    frame.payload,
+                    &data[frame.offset..(frame.offset + frame.payload.len())]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_msg_start_ringbuf() {
+        assert_eq!(
+            Ok(Some(7)),
+            msg_start_ringbuf("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4)
+        );
+        assert_eq!(
+            Err(ItemError::NotAnItem('1')),
+            msg_start_ringbuf("ab1cd", 4)
+        );
+    }
+
+    #[test]
+    fn test_marker_end_matches_ringbuf() {
+        for header_size in [4, 14, 30] {
+            let input = bench_input(2000, header_size);
+            assert_eq!(None, marker_end(input.as_bytes(), header_size));
+            assert_eq!(Ok(None), msg_start_ringbuf(&input, header_size));
+
+            let input = format!("{input}{}", bench_input(2000, header_size + 1));
+            assert_eq!(
+                Ok(marker_end(input.as_bytes(), header_size)),
+                msg_start_ringbuf(&input, header_size)
+            );
+        }
+    }
+
+    #[test]
+    fn test_frames_main() {
+        assert!(frames_main(&[]).is_err());
+        let missing = "/nonexistent/day-06-frames.bin".to_string();
+        assert!(frames_main(std::slice::from_ref(&missing))
+            .unwrap_err()
+            .starts_with(&missing));
+
+        let path = std::env::temp_dir().join(format!("day-06-frames-{}.bin", std::process::id()));
+        let data = encode_frames(&[b"aaaa".to_vec(), b"bb".to_vec()]);
+        std::fs::write(&path, &data).unwrap();
+        let frames = frames_main(&[path.to_str().unwrap().to_string()]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let expected: String = FrameDecoder::new(&data)
+            .map(|f| format!("{}: {}\n", f.offset, String::from_utf8_lossy(f.payload)))
+            .collect();
+        assert_eq!(expected, frames);
+        assert_eq!(2, frames.lines().count());
+    }
 }
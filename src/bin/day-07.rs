@@ -1,19 +1,12 @@
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::iter::Peekable;
 use std::rc::{Rc, Weak};
-use std::str::FromStr;
 use std::str::Lines;
 
 use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    bytes::complete::take_till,
-    character::complete::{alpha1, newline},
-    character::is_space,
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
+    branch::alt, bytes::complete::tag, bytes::complete::take_till, character::complete::alpha1,
+    sequence::separated_pair, IResult,
 };
 
 #[derive(Debug, PartialEq)]
@@ -53,7 +46,7 @@ impl Directory {
 
     pub fn child_of(parent: &Rc<Directory>) -> Directory {
         Directory {
-            parent: Rc::downgrade(&parent),
+            parent: Rc::downgrade(parent),
             children: RefCell::new(HashMap::new()),
             files: RefCell::new(HashMap::new()),
         }
@@ -101,7 +94,7 @@ impl Iterator for DirectoryBfsIterator {
     }
 }
 
-fn parse_cd(input: &str) -> IResult<&str, Command> {
+fn parse_cd(input: &str) -> IResult<&str, Command<'_>> {
     let (remainder, _) = tag("$ cd ")(input)?;
     let (remainder, dirname) = alt((tag(".."), tag("/"), alpha1))(remainder)?;
     let cd = match dirname {
@@ -112,17 +105,17 @@ fn parse_cd(input: &str) -> IResult<&str, Command> {
     Ok((remainder, Command::Cd(cd)))
 }
 
-fn parse_ls(input: &str) -> IResult<&str, Command> {
+fn parse_ls(input: &str) -> IResult<&str, Command<'_>> {
     let (remainder, _) = tag("$ ls")(input)?;
     Ok((remainder, Command::Ls))
 }
 
-fn parse_dir(input: &str) -> IResult<&str, ListLine> {
+fn parse_dir(input: &str) -> IResult<&str, ListLine<'_>> {
     let (remainder, _) = tag("dir ")(input)?;
     Ok(("", ListLine::Dir(remainder)))
 }
 
-fn parse_file(input: &str) -> IResult<&str, ListLine> {
+fn parse_file(input: &str) -> IResult<&str, ListLine<'_>> {
     let (remainder, (size, name)) = separated_pair(
         nom::character::complete::i64,
         tag(" "),
@@ -141,7 +134,7 @@ fn handle_cd(cd: Cd, pwd: Rc<Directory>) -> Rc<Directory> {
             root
         }
         Cd::Parent => pwd.parent.upgrade().unwrap_or(pwd),
-        Cd::Child(dname) => Rc::clone(&pwd.children.borrow().get(dname).unwrap()),
+        Cd::Child(dname) => Rc::clone(pwd.children.borrow().get(dname).unwrap()),
     }
 }
 
@@ -159,10 +152,10 @@ fn handle_ls(lines: &mut Peekable<Lines>, pwd: &Rc<Directory>) {
             }
         };
 
-        let (remainder, line) = alt((parse_dir, parse_file))(lines.next().unwrap()).unwrap();
+        let (_remainder, line) = alt((parse_dir, parse_file))(lines.next().unwrap()).unwrap();
         match line {
             ListLine::Dir(name) => {
-                let _ = children.insert(name.to_owned(), Rc::new(Directory::child_of(&pwd)));
+                let _ = children.insert(name.to_owned(), Rc::new(Directory::child_of(pwd)));
             }
             ListLine::File(size, name) => {
                 let _ = files.insert(name.to_owned(), size);
@@ -178,7 +171,7 @@ fn build_fs(input: &str) -> Rc<Directory> {
     assert_eq!(Some("$ cd /"), lines.next());
 
     while let Some(line) = lines.next() {
-        let (remainder, cmd) = alt((parse_cd, parse_ls))(line).unwrap();
+        let (_remainder, cmd) = alt((parse_cd, parse_ls))(line).unwrap();
         match cmd {
             Command::Cd(cd) => {
                 pwd = handle_cd(cd, Rc::clone(&pwd));
@@ -49,18 +49,18 @@ impl<T> Grid<T> {
         }
     }
 
-    pub fn rows(&self) -> RowsIterator<T> {
+    pub fn rows(&self) -> RowsIterator<'_, T> {
         RowsIterator { grid: self, row: 0 }
     }
 
-    pub fn columns(&self) -> ColumnsIterator<T> {
+    pub fn columns(&self) -> ColumnsIterator<'_, T> {
         ColumnsIterator {
             grid: self,
             column: 0,
         }
     }
 
-    pub fn row(&self, n_row: usize) -> RowIterator<T> {
+    pub fn row(&self, n_row: usize) -> RowIterator<'_, T> {
         RowIterator {
             grid: self,
             row: n_row,
@@ -69,7 +69,7 @@ impl<T> Grid<T> {
         }
     }
 
-    pub fn column(&self, n_column: usize) -> ColumnIterator<T> {
+    pub fn column(&self, n_column: usize) -> ColumnIterator<'_, T> {
         ColumnIterator {
             grid: self,
             front_row: 0,
@@ -263,7 +263,7 @@ where
                 };
 
             // i16 assumes the len of the row/column is under 2^15.
-            let mut height_to_index = [0 as i16; MAX_HEIGHT + 1];
+            let mut height_to_index = [0_i16; MAX_HEIGHT + 1];
             zip(giter, aiter)
                 .enumerate()
                 .for_each(|(index, (&height, scenic_score))| {
@@ -281,7 +281,7 @@ where
 
 fn fill_auxiliary_grid_part2(grid: &Grid<i8>, auxiliary_grid: &Grid<AtomicI64>) {
     // Probably easier to use rayon::scope & spawn, but rayon recommends par_iter instead :P.
-    let counters: [Box<dyn Fn() -> () + Send + Sync>; 4] = [
+    let counters: [Box<dyn Fn() + Send + Sync>; 4] = [
         // ParIter over each row &  column both forwards and backwards.
         Box::new(|| apply_part2_line(grid.rows(), auxiliary_grid.rows(), false)),
         Box::new(|| apply_part2_line(grid.rows(), auxiliary_grid.rows(), true)),
@@ -293,7 +293,7 @@ fn fill_auxiliary_grid_part2(grid: &Grid<i8>, auxiliary_grid: &Grid<AtomicI64>)
 
 fn parse_row(src: &str, dst: &mut [i8]) {
     for (i, c) in src.chars().enumerate() {
-        dst[i] = i8::from_str_radix(&c.to_string(), 10).unwrap();
+        dst[i] = c.to_string().parse::<i8>().unwrap();
     }
 }
 
@@ -370,9 +370,9 @@ mod tests {
     #[test]
     fn test_column() {
         let grid: Grid<i8> = Grid::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
-        assert_eq!(grid.column(2).map(|i| *i).collect::<Vec<i8>>(), vec![3, 6]);
+        assert_eq!(grid.column(2).copied().collect::<Vec<i8>>(), vec![3, 6]);
         assert_eq!(
-            grid.column(2).rev().map(|i| *i).collect::<Vec<i8>>(),
+            grid.column(2).rev().copied().collect::<Vec<i8>>(),
             vec![6, 3]
         );
     }
@@ -380,9 +380,9 @@ mod tests {
     #[test]
     fn test_row() {
         let grid = Grid::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
-        assert_eq!(grid.row(0).map(|i| *i).collect::<Vec<i8>>(), vec![1, 2, 3]);
+        assert_eq!(grid.row(0).copied().collect::<Vec<i8>>(), vec![1, 2, 3]);
         assert_eq!(
-            grid.row(0).rev().map(|i| *i).collect::<Vec<i8>>(),
+            grid.row(0).rev().copied().collect::<Vec<i8>>(),
             vec![3, 2, 1]
         );
     }
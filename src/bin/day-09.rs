@@ -7,6 +7,10 @@ enum Direction {
     Left,
     Up,
     Down,
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
 }
 
 impl FromStr for Direction {
@@ -18,68 +22,170 @@ impl FromStr for Direction {
             "L" => Ok(Direction::Left),
             "U" => Ok(Direction::Up),
             "D" => Ok(Direction::Down),
+            "UR" => Ok(Direction::UpRight),
+            "UL" => Ok(Direction::UpLeft),
+            "DR" => Ok(Direction::DownRight),
+            "DL" => Ok(Direction::DownLeft),
             _ => Err(Error),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
+impl Direction {
+    // The change in position from one step in this direction.
+    fn delta(&self) -> Point {
+        match self {
+            Direction::Right => Point::new(1, 0),
+            Direction::Left => Point::new(-1, 0),
+            Direction::Up => Point::new(0, 1),
+            Direction::Down => Point::new(0, -1),
+            Direction::UpRight => Point::new(1, 1),
+            Direction::UpLeft => Point::new(-1, 1),
+            Direction::DownRight => Point::new(1, -1),
+            Direction::DownLeft => Point::new(-1, -1),
+        }
+    }
+}
+
+// A point on an N dimensional grid. The puzzle is 2D, with x to the right and y up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Point<const N: usize = 2> {
+    coords: [i32; N],
+}
+
+impl<const N: usize> Default for Point<N> {
+    fn default() -> Self {
+        Point { coords: [0; N] }
+    }
+}
+
+impl Point {
+    const fn new(x: i32, y: i32) -> Self {
+        Point { coords: [x, y] }
+    }
+
+    fn x(&self) -> i32 {
+        self.coords[0]
+    }
+
+    fn y(&self) -> i32 {
+        self.coords[1]
+    }
+}
+
+impl<const N: usize> std::ops::Add for Point<N> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        for (c, d) in self.coords.iter_mut().zip(rhs.coords) {
+            *c += d;
+        }
+        self
+    }
 }
 
 impl std::ops::Add<Direction> for Point {
     type Output = Self;
 
     fn add(self, rhs: Direction) -> Self::Output {
-        match rhs {
-            Direction::Right => Point {
-                x: self.x + 1,
-                y: self.y,
-            },
-            Direction::Left => Point {
-                x: self.x - 1,
-                y: self.y,
-            },
-            Direction::Up => Point {
-                x: self.x,
-                y: self.y + 1,
-            },
-            Direction::Down => Point {
-                x: self.x,
-                y: self.y - 1,
-            },
-        }
+        self + rhs.delta()
     }
 }
 
-impl Point {
-    fn move_to(&mut self, Point { x, y }: &Point) {
-        self.x = *x;
-        self.y = *y;
+impl<const N: usize> Point<N> {
+    // Whether every coordinate is within `distance` of `other`'s.
+    fn within(&self, other: &Point<N>, distance: i32) -> bool {
+        self.coords
+            .iter()
+            .zip(other.coords)
+            .all(|(c, o)| (c - o).abs() <= distance)
     }
 
-    fn maybe_move_towards(&mut self, Point { x, y }: &Point) {
-        if (self.x - x).abs() < 2 && (self.y - y).abs() < 2 {
+    fn maybe_move_towards(&mut self, lead: &Point<N>) {
+        if self.within(lead, 1) {
             return;
         }
 
-        if (self.x - x) >= 2 {
-            self.x = x + 1;
-        } else if (self.x - x) <= -2 {
-            self.x = x - 1;
-        } else {
-            self.x = *x;
+        for (c, l) in self.coords.iter_mut().zip(lead.coords) {
+            if *c - l >= 2 {
+                *c = l + 1;
+            } else if *c - l <= -2 {
+                *c = l - 1;
+            } else {
+                *c = l;
+            }
         }
+    }
+}
+
+// How a knot reacts when the knot ahead of it moves.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum FollowRule {
+    // The puzzle's rule: once no longer touching, step straight or diagonally towards the lead.
+    #[default]
+    Touching,
+    // Like Touching, but only ever moves along one axis at a time.
+    Manhattan,
+    // Like Touching, but allows this many cells of slack between knots before pulling.
+    Elastic(i32),
+    // Once no longer touching, move to where the lead was before its last move.
+    Lagging,
+}
 
-        if (self.y - y) >= 2 {
-            self.y = y + 1;
-        } else if (self.y - y) <= -2 {
-            self.y = y - 1;
-        } else {
-            self.y = *y;
+impl FromStr for FollowRule {
+    type Err = String;
+
+    // "touching", "manhattan", "lagging" or "elastic:K".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "touching" => Ok(FollowRule::Touching),
+            None if s == "manhattan" => Ok(FollowRule::Manhattan),
+            None if s == "lagging" => Ok(FollowRule::Lagging),
+            Some(("elastic", slack)) => match slack.parse() {
+                Ok(slack) if slack >= 0 => Ok(FollowRule::Elastic(slack)),
+                _ => Err(format!("Invalid slack {slack:?}")),
+            },
+            _ => Err(format!("Unknown follow rule {s:?}")),
+        }
+    }
+}
+
+impl FollowRule {
+    // Where `knot` ends up after its lead moved from `lead_before` to `lead`.
+    fn follow<const N: usize>(
+        &self,
+        knot: &Point<N>,
+        lead: &Point<N>,
+        lead_before: &Point<N>,
+    ) -> Point<N> {
+        let mut knot = *knot;
+        match self {
+            FollowRule::Touching => knot.maybe_move_towards(lead),
+            FollowRule::Manhattan => {
+                while !knot.within(lead, 1) {
+                    let (axis, diff) = (0..N)
+                        .map(|d| (d, lead.coords[d] - knot.coords[d]))
+                        .rev()
+                        .max_by_key(|(_, diff)| diff.abs())
+                        .unwrap();
+                    knot.coords[axis] += diff.signum();
+                }
+            }
+            FollowRule::Elastic(slack) => {
+                let reach = 1 + slack;
+                if !knot.within(lead, reach) {
+                    for (c, l) in knot.coords.iter_mut().zip(lead.coords) {
+                        *c = (*c - (*c - l).signum()).clamp(l - reach, l + reach);
+                    }
+                }
+            }
+            FollowRule::Lagging => {
+                if !knot.within(lead, 1) {
+                    knot = *lead_before;
+                }
+            }
         }
+        knot
     }
 }
 
@@ -90,94 +196,407 @@ fn parse_row(line: &str) -> (Direction, i32) {
 }
 
 fn parse_input(input: &str) -> Vec<(Direction, i32)> {
-    input.lines().map(|line| parse_row(line)).collect()
-}
-
-fn part1(input: &str) -> HashSet<(i32, i32)> {
-    let movements = parse_input(input);
-    let (mut hx, mut hy, mut tx, mut ty) = (0 as i32, 0 as i32, 0, 0);
-    let mut tail_locations = HashSet::new();
-    tail_locations.insert((tx, ty));
-
-    for (direction, steps) in movements {
-        for i in 0..steps {
-            match direction {
-                Direction::Right => hx += 1,
-                Direction::Left => hx -= 1,
-                Direction::Up => hy += 1,
-                Direction::Down => hy -= 1,
-            }
+    input.lines().map(parse_row).collect()
+}
+
+// A rope of knots where knot 0 is the head and every other knot follows the one ahead of it. Tracks
+// the positions every knot has visited, all knots starting at the origin.
+struct Rope<const N: usize = 2> {
+    knots: Vec<Point<N>>,
+    visited: Vec<HashSet<Point<N>>>,
+    rule: FollowRule,
+}
+
+impl Rope {
+    pub fn new(n_knots: usize) -> Self {
+        Self::with_knots(n_knots)
+    }
 
-            if (tx - hx).abs() < 2 && (ty - hy).abs() < 2 {
-                continue;
+    // Move the head one step and let the rest of the rope follow.
+    pub fn step(&mut self, direction: Direction) {
+        self.step_by(direction.delta());
+    }
+
+    pub fn run(&mut self, movements: &[(Direction, i32)]) {
+        for direction in head_steps(movements) {
+            self.step(direction);
+        }
+    }
+}
+
+impl<const N: usize> Rope<N> {
+    pub fn with_knots(n_knots: usize) -> Self {
+        assert!(n_knots > 0, "A rope needs at least one knot");
+        Rope {
+            knots: vec![Point::default(); n_knots],
+            visited: vec![HashSet::from([Point::default()]); n_knots],
+            rule: FollowRule::default(),
+        }
+    }
+
+    pub fn with_rule(mut self, rule: FollowRule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    // Move the head by `delta` and let the rest of the rope follow.
+    pub fn step_by(&mut self, delta: Point<N>) {
+        // Where the knot ahead was before this step, for `FollowRule::Lagging`.
+        let mut lead_before = self.knots[0];
+        self.knots[0] = self.knots[0] + delta;
+        for i in 1..self.knots.len() {
+            let knot = self.knots[i];
+            self.knots[i] = self.rule.follow(&knot, &self.knots[i - 1], &lead_before);
+            lead_before = knot;
+        }
+        for (knot, visited) in self.knots.iter().zip(self.visited.iter_mut()) {
+            visited.insert(*knot);
+        }
+    }
+
+    // Every position the given knot has been in. Knot 0 is the head.
+    pub fn visited(&self, knot: usize) -> &HashSet<Point<N>> {
+        &self.visited[knot]
+    }
+
+    pub fn tail_visited(&self) -> &HashSet<Point<N>> {
+        self.visited(self.knots.len() - 1)
+    }
+
+    // Step through all of `deltas`, recording every knot after every step.
+    pub fn trace(&mut self, deltas: impl IntoIterator<Item = Point<N>>) -> Trace<N> {
+        let mut frames = vec![self.knots.clone()];
+        for delta in deltas {
+            self.step_by(delta);
+            frames.push(self.knots.clone());
+        }
+        Trace { frames }
+    }
+}
+
+// The position of every knot after every step. Frame 0 is the state before the first step.
+#[derive(Debug, Clone, PartialEq)]
+struct Trace<const N: usize = 2> {
+    frames: Vec<Vec<Point<N>>>,
+}
+
+impl<const N: usize> Trace<N> {
+    fn axis_name(d: usize) -> String {
+        match d {
+            0..=3 => ["x", "y", "z", "w"][d].to_string(),
+            _ => format!("c{d}"),
+        }
+    }
+
+    // (step, knot, position) for every knot of every frame.
+    fn rows(&self) -> impl Iterator<Item = (usize, usize, &Point<N>)> {
+        self.frames.iter().enumerate().flat_map(|(step, knots)| {
+            knots
+                .iter()
+                .enumerate()
+                .map(move |(knot, point)| (step, knot, point))
+        })
+    }
+
+    // A header of `step,knot,x,y`, then one row per knot per frame.
+    fn write_csv(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let axes: Vec<String> = (0..N).map(Self::axis_name).collect();
+        writeln!(out, "step,knot,{}", axes.join(","))?;
+        for (step, knot, point) in self.rows() {
+            let coords: Vec<String> = point.coords.iter().map(i32::to_string).collect();
+            writeln!(out, "{step},{knot},{}", coords.join(","))?;
+        }
+        Ok(())
+    }
+
+    // One JSON object per knot per frame, e.g. {"step":0,"knot":0,"x":0,"y":0}.
+    fn write_jsonl(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        for (step, knot, point) in self.rows() {
+            let coords: Vec<String> = point
+                .coords
+                .iter()
+                .enumerate()
+                .map(|(d, c)| format!("\"{}\":{c}", Self::axis_name(d)))
+                .collect();
+            writeln!(
+                out,
+                "{{\"step\":{step},\"knot\":{knot},{}}}",
+                coords.join(",")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Inclusive rectangle of the grid to draw.
+#[derive(Debug, Clone, PartialEq)]
+struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Bounds {
+    // The smallest rectangle holding the origin and all the given points.
+    fn around<'a>(points: impl IntoIterator<Item = &'a Point>) -> Self {
+        let mut bounds = Bounds {
+            min: Point::default(),
+            max: Point::default(),
+        };
+        for p in points {
+            for d in 0..2 {
+                bounds.min.coords[d] = bounds.min.coords[d].min(p.coords[d]);
+                bounds.max.coords[d] = bounds.max.coords[d].max(p.coords[d]);
             }
+        }
+        bounds
+    }
 
-            if (tx - hx) >= 2 {
-                tx = hx + 1;
-            } else if (tx - hx) <= -2 {
-                tx = hx - 1;
-            } else {
-                tx = hx;
+    // Draw one char per cell, with up at the top like the puzzle.
+    fn render(&self, cell: impl Fn(&Point) -> char) -> String {
+        let mut out = String::new();
+        for y in (self.min.y()..=self.max.y()).rev() {
+            for x in self.min.x()..=self.max.x() {
+                out.push(cell(&Point::new(x, y)));
             }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Rope {
+    // Bounds covering everywhere any knot has been.
+    fn bounds(&self) -> Bounds {
+        Bounds::around(self.visited.iter().flatten())
+    }
+
+    // The knots in the puzzle's style: `H` for the head, then `1`..`9` (`+` past that), `s` for
+    // the start and `.` elsewhere. Knots closer to the head are drawn on top.
+    fn render_knots(&self, bounds: &Bounds) -> String {
+        bounds.render(|p| match self.knots.iter().position(|k| k == p) {
+            Some(0) => 'H',
+            Some(i) => std::char::from_digit(i as u32, 10).unwrap_or('+'),
+            None if *p == Point::default() => 's',
+            None => '.',
+        })
+    }
 
-            if (ty - hy) >= 2 {
-                ty = hy + 1;
-            } else if (ty - hy) <= -2 {
-                ty = hy - 1;
+    // `#` for every position the knot has visited, `s` for the start.
+    fn render_visited(&self, knot: usize, bounds: &Bounds) -> String {
+        bounds.render(|p| {
+            if *p == Point::default() {
+                's'
+            } else if self.visited[knot].contains(p) {
+                '#'
             } else {
-                ty = hy;
+                '.'
             }
+        })
+    }
+}
 
-            tail_locations.insert((tx, ty));
-        }
+// Every single step of the head, in order. A negative number of steps moves nowhere.
+fn head_steps(movements: &[(Direction, i32)]) -> impl Iterator<Item = Direction> + '_ {
+    movements.iter().flat_map(|(direction, steps)| {
+        std::iter::repeat_n(*direction, usize::try_from(*steps).unwrap_or(0))
+    })
+}
+
+// Usage: day-09 render N_KNOTS [STEP]
+// Draws the rope after STEP single steps of the head (default all of them) and the tail's trail.
+fn render_main(txt: &str, args: &[String]) -> Result<String, String> {
+    let n_knots: usize = args
+        .first()
+        .ok_or("Missing knot count")?
+        .parse()
+        .map_err(|e| format!("Invalid knot count: {e}"))?;
+    if n_knots == 0 {
+        return Err("A rope needs at least one knot".to_string());
     }
+    let n_steps: usize = args
+        .get(1)
+        .map_or(Ok(usize::MAX), |n| n.parse())
+        .map_err(|e| format!("Invalid step: {e}"))?;
+    let movements = parse_input(txt);
+    let mut rope = Rope::new(n_knots);
+    for direction in head_steps(&movements).take(n_steps) {
+        rope.step(direction);
+    }
+    let bounds = rope.bounds();
+    Ok(format!(
+        "{}\n{}",
+        rope.render_knots(&bounds),
+        rope.render_visited(n_knots - 1, &bounds)
+    ))
+}
 
-    tail_locations
+fn tail_locations(input: &str, n_knots: usize) -> HashSet<Point> {
+    let mut rope = Rope::new(n_knots);
+    rope.run(&parse_input(input));
+    rope.tail_visited().clone()
 }
 
-fn part2(input: &str) -> HashSet<(i32, i32)> {
-    const ROPE_LEN: usize = 10;
-    let movements = parse_input(input);
-    let mut tail_locations = HashSet::new();
-    tail_locations.insert((0, 0));
+fn part1(input: &str) -> HashSet<Point> {
+    tail_locations(input, 2)
+}
 
-    let mut rope: [Point; ROPE_LEN] = core::array::from_fn(|_| Point::default());
-    for (direction, steps) in movements {
-        for i in 0..steps {
-            rope[0] = rope[0].clone() + direction;
-            for i in (1..ROPE_LEN) {
-                let (lead, follow) = rope.split_at_mut(i);
-                follow[0].maybe_move_towards(lead.last_mut().unwrap());
-            }
+fn part2(input: &str) -> HashSet<Point> {
+    tail_locations(input, 10)
+}
 
-            let tail = rope.last().unwrap();
-            tail_locations.insert((tail.x, tail.y));
-        }
+// Usage: day-09 rope N_KNOTS [KNOT]
+// Prints how many positions KNOT (default the tail) visited.
+fn rope_main(txt: &str, args: &[String]) -> Result<usize, String> {
+    let n_knots: usize = args
+        .first()
+        .ok_or("Missing knot count")?
+        .parse()
+        .map_err(|e| format!("Invalid knot count: {e}"))?;
+    if n_knots == 0 {
+        return Err("A rope needs at least one knot".to_string());
+    }
+    let knot: usize = args
+        .get(1)
+        .map_or(Ok(n_knots - 1), |k| k.parse())
+        .map_err(|e| format!("Invalid knot: {e}"))?;
+    if knot >= n_knots {
+        return Err(format!("Knot {knot} is not in a rope of {n_knots} knots"));
+    }
+    let mut rope = Rope::new(n_knots);
+    rope.run(&parse_input(txt));
+    Ok(rope.visited(knot).len())
+}
+
+// Usage: day-09 trace N_KNOTS [csv|jsonl] [RULE]
+// Writes every knot position after every step to `out`.
+fn trace_main(txt: &str, args: &[String], out: &mut impl std::io::Write) -> Result<(), String> {
+    let n_knots: usize = args
+        .first()
+        .ok_or("Missing knot count")?
+        .parse()
+        .map_err(|e| format!("Invalid knot count: {e}"))?;
+    if n_knots == 0 {
+        return Err("A rope needs at least one knot".to_string());
     }
+    let rule = args
+        .get(2)
+        .map_or(Ok(FollowRule::default()), |r| r.parse())?;
+    let movements = parse_input(txt);
+    let trace = Rope::new(n_knots)
+        .with_rule(rule)
+        .trace(head_steps(&movements).map(|d| d.delta()));
+    match args.get(1).map_or("csv", String::as_str) {
+        "csv" => trace.write_csv(out),
+        "jsonl" => trace.write_jsonl(out),
+        other => return Err(format!("Unknown trace format {other:?}")),
+    }
+    .map_err(|e| e.to_string())
+}
 
-    tail_locations
+// Usage: day-09 rules N_KNOTS [RULE...]
+// Compares how many positions the tail covers under each follow rule.
+fn rules_main(txt: &str, args: &[String]) -> Result<Vec<(FollowRule, usize)>, String> {
+    let n_knots: usize = args
+        .first()
+        .ok_or("Missing knot count")?
+        .parse()
+        .map_err(|e| format!("Invalid knot count: {e}"))?;
+    if n_knots == 0 {
+        return Err("A rope needs at least one knot".to_string());
+    }
+    let rules = if args.len() > 1 {
+        args[1..]
+            .iter()
+            .map(|rule| rule.parse())
+            .collect::<Result<Vec<FollowRule>, _>>()?
+    } else {
+        vec![
+            FollowRule::Touching,
+            FollowRule::Manhattan,
+            FollowRule::Elastic(1),
+            FollowRule::Elastic(2),
+            FollowRule::Lagging,
+        ]
+    };
+    let movements = parse_input(txt);
+    Ok(rules
+        .into_iter()
+        .map(|rule| {
+            let mut rope = Rope::new(n_knots).with_rule(rule);
+            rope.run(&movements);
+            (rule, rope.tail_visited().len())
+        })
+        .collect())
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.extend(vec!["data", "day-09.txt"]);
     println!("{:?}", path);
     let txt = std::fs::read_to_string(path).unwrap();
 
-    println!("9");
-    println!("{:?}", part1(&txt).len());
-    println!("{:?}", part2(&txt).len());
+    match args.first().map(String::as_str) {
+        Some("render") => match render_main(&txt, &args[1..]) {
+            Ok(drawing) => print!("{drawing}"),
+            Err(e) => println!("{e}"),
+        },
+        Some("trace") => {
+            if let Err(e) = trace_main(&txt, &args[1..], &mut std::io::stdout().lock()) {
+                println!("{e}");
+            }
+        }
+        Some("rules") => match rules_main(&txt, &args[1..]) {
+            Ok(coverage) => {
+                for (rule, visited) in coverage {
+                    println!("{rule:?}: {visited}");
+                }
+            }
+            Err(e) => println!("{e}"),
+        },
+        Some("rope") => println!("{:?}", rope_main(&txt, &args[1..])),
+        _ => {
+            println!("9");
+            println!("{:?}", part1(&txt).len());
+            println!("{:?}", part2(&txt).len());
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // The puzzle's example.
+    static INPUT: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2";
+
+    // The puzzle's larger example for part 2.
+    static INPUT2: &str = "R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20";
+
+    // Command line arguments as `main` passes them to the subcommands.
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
     #[test]
     fn test_parse_input() {
         assert_eq!(
             vec![
-                (Direction::Right, 4 as i32),
+                (Direction::Right, 4_i32),
                 (Direction::Up, 4),
                 (Direction::Down, 1),
                 (Direction::Left, 5)
@@ -193,42 +612,314 @@ L 5"
 
     #[test]
     fn test_part1() {
-        let locs = part1(
-            "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2",
-        );
+        let locs = part1(INPUT);
         assert_eq!(13, locs.len());
     }
 
     #[test]
     fn test_part2a() {
-        const INPUT: &str = "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2";
         assert_eq!(part2(INPUT).len(), 1);
     }
 
     #[test]
     fn test_part2b() {
-        const INPUT: &str = "R 5
-U 8
-L 8
-D 3
-R 17
-D 10
-L 25
-U 20";
-        assert_eq!(part2(INPUT).len(), 36);
+        assert_eq!(part2(INPUT2).len(), 36);
+    }
+
+    #[test]
+    fn test_rope() {
+        let mut rope = Rope::new(10);
+        rope.run(&parse_input(INPUT));
+        // The second knot of a 10 knot rope moves just like the tail of a 2 knot rope.
+        assert_eq!(&part1(INPUT), rope.visited(1));
+        assert_eq!(&part2(INPUT), rope.tail_visited());
+        assert_eq!(Point::new(2, 2), rope.knots[0]);
+        assert_eq!(Point::new(1, 2), rope.knots[1]);
+
+        let mut head_only = Rope::new(1);
+        head_only.step(Direction::Up);
+        head_only.step(Direction::Up);
+        assert_eq!(3, head_only.tail_visited().len());
+    }
+
+    #[test]
+    fn test_rope_main() {
+        assert_eq!(Ok(13), rope_main(INPUT, &args(&["2"])));
+        assert_eq!(Ok(5), rope_main("R 4", &args(&["3", "0"])));
+        assert!(rope_main("R 4", &args(&["3", "3"])).is_err());
+        assert!(rope_main("R 4", &args(&["0"])).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let mut rope = Rope::new(2);
+        rope.run(&parse_input(INPUT));
+        let bounds = rope.bounds();
+        assert_eq!(
+            Bounds {
+                min: Point::new(0, 0),
+                max: Point::new(5, 4)
+            },
+            bounds
+        );
+        assert_eq!(
+            "..##..
+...##.
+.####.
+....#.
+s###..
+",
+            rope.render_visited(1, &bounds)
+        );
+        assert_eq!(
+            "......
+......
+.1H...
+......
+s.....
+",
+            rope.render_knots(&bounds)
+        );
+
+        // Part 2's second example after "R 5", drawn on the puzzle's grid.
+        let mut rope = Rope::new(10);
+        rope.run(&[(Direction::Right, 5)]);
+        let bounds = Bounds {
+            min: Point::new(-11, -5),
+            max: Point::new(14, 15),
+        };
+        let drawing = rope.render_knots(&bounds);
+        assert_eq!(
+            "...........54321H.........",
+            drawing.lines().nth(15).unwrap()
+        );
+        // Knot 5 covers the rest of the rope and the start.
+        assert!(!drawing.contains('s'));
+    }
+
+    #[test]
+    fn test_render_main() {
+        assert_eq!(
+            Ok("H1s\n\n.#s\n".to_string()),
+            render_main("L 2\nR 4", &args(&["2", "2"]))
+        );
+        assert!(render_main("R 1", &args(&["0"])).is_err());
+    }
+
+    #[test]
+    fn test_diagonals() {
+        assert_eq!(
+            vec![
+                (Direction::UpRight, 3),
+                (Direction::DownLeft, 1),
+                (Direction::UpLeft, 2),
+                (Direction::DownRight, 4)
+            ],
+            parse_input("UR 3\nDL 1\nUL 2\nDR 4")
+        );
+        assert!("RU".parse::<Direction>().is_err());
+
+        let mut rope = Rope::new(2);
+        rope.run(&parse_input("UR 3"));
+        assert_eq!(Point::new(3, 3), rope.knots[0]);
+        assert_eq!(Point::new(2, 2), rope.knots[1]);
+        assert_eq!(3, rope.tail_visited().len());
+
+        // A diagonal step is the same as the two straight steps, as far as the head goes.
+        let mut straight = Rope::new(1);
+        straight.run(&parse_input("U 1\nR 1"));
+        assert_eq!(straight.knots[0], Point::default() + Direction::UpRight);
+    }
+
+    #[test]
+    fn test_3d_rope() {
+        let mut rope = Rope::<3>::with_knots(3);
+        for _ in 0..4 {
+            rope.step_by(Point { coords: [1, 0, 1] });
+        }
+        assert_eq!(Point { coords: [4, 0, 4] }, rope.knots[0]);
+        assert_eq!(Point { coords: [3, 0, 3] }, rope.knots[1]);
+        assert_eq!(Point { coords: [2, 0, 2] }, rope.knots[2]);
+        assert_eq!(3, rope.tail_visited().len());
+
+        // Following in 3D with one axis held at 0 matches the 2D rope.
+        let mut flat = Rope::new(3);
+        flat.run(&parse_input("UR 4"));
+        assert_eq!(rope.tail_visited().len(), flat.tail_visited().len());
+
+        let mut tail = Point { coords: [0, 0, 0] };
+        tail.maybe_move_towards(&Point { coords: [1, 2, -1] });
+        assert_eq!(Point { coords: [1, 1, -1] }, tail);
+        tail.maybe_move_towards(&Point { coords: [2, 2, 0] });
+        assert_eq!(Point { coords: [1, 1, -1] }, tail);
+    }
+
+    #[test]
+    fn test_follow_rule_parse() {
+        assert_eq!(Ok(FollowRule::Touching), "touching".parse());
+        assert_eq!(Ok(FollowRule::Manhattan), "manhattan".parse());
+        assert_eq!(Ok(FollowRule::Lagging), "lagging".parse());
+        assert_eq!(Ok(FollowRule::Elastic(3)), "elastic:3".parse());
+        assert!("elastic:-1".parse::<FollowRule>().is_err());
+        assert!("elastic".parse::<FollowRule>().is_err());
+        assert!("springy".parse::<FollowRule>().is_err());
+    }
+
+    #[test]
+    fn test_follow_rules() {
+        let origin = Point::default();
+        let lead = Point::new(2, 1);
+        let lead_before = Point::new(1, 1);
+        assert_eq!(
+            Point::new(1, 1),
+            FollowRule::Touching.follow(&origin, &lead, &lead_before)
+        );
+        // Closes the bigger gap first, and never moves diagonally.
+        assert_eq!(
+            Point::new(1, 0),
+            FollowRule::Manhattan.follow(&origin, &lead, &lead_before)
+        );
+        assert_eq!(
+            Point::new(1, 1),
+            FollowRule::Lagging.follow(&origin, &lead, &lead_before)
+        );
+        assert_eq!(
+            origin,
+            FollowRule::Elastic(1).follow(&origin, &lead, &lead_before)
+        );
+        assert_eq!(
+            Point::new(2, 1),
+            FollowRule::Elastic(1).follow(&origin, &Point::new(4, 1), &Point::new(3, 1))
+        );
+        // Touching knots never move.
+        for rule in [
+            FollowRule::Touching,
+            FollowRule::Manhattan,
+            FollowRule::Elastic(2),
+            FollowRule::Lagging,
+        ] {
+            assert_eq!(
+                origin,
+                rule.follow(&origin, &Point::new(1, -1), &Point::new(0, -1))
+            );
+        }
+    }
+
+    #[test]
+    fn test_rope_rules() {
+        let coverage = |n_knots, rule| {
+            let mut rope = Rope::new(n_knots).with_rule(rule);
+            rope.run(&parse_input(INPUT));
+            rope.tail_visited().len()
+        };
+        assert_eq!(13, coverage(2, FollowRule::default()));
+        assert_eq!(13, coverage(2, FollowRule::Elastic(0)));
+        // With one knot following orthogonal head moves, lagging is the puzzle's rule.
+        assert_eq!(13, coverage(2, FollowRule::Lagging));
+        assert_eq!(1, coverage(10, FollowRule::Touching));
+        // Each lagging knot takes the place the knot ahead of it just left.
+        let mut rope = Rope::new(3).with_rule(FollowRule::Lagging);
+        let trace = rope.trace([Point::new(1, 0); 3]);
+        assert_eq!(
+            Some(&vec![Point::new(3, 0), Point::new(2, 0), Point::new(1, 0)]),
+            trace.frames.last()
+        );
+        assert!(coverage(2, FollowRule::Elastic(1)) < 13);
+
+        assert_eq!(
+            Ok(vec![(FollowRule::Touching, 13), (FollowRule::Lagging, 13)]),
+            rules_main(INPUT, &args(&["2", "touching", "lagging"]))
+        );
+        assert_eq!(5, rules_main(INPUT, &args(&["10"])).unwrap().len());
+        assert!(rules_main(INPUT, &args(&["2", "bouncy"])).is_err());
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut rope = Rope::new(2);
+        let trace = rope.trace(head_steps(&parse_input("R 2\nU 1\nU 1")).map(|d| d.delta()));
+        assert_eq!(
+            vec![
+                vec![Point::new(0, 0), Point::new(0, 0)],
+                vec![Point::new(1, 0), Point::new(0, 0)],
+                vec![Point::new(2, 0), Point::new(1, 0)],
+                vec![Point::new(2, 1), Point::new(1, 0)],
+                vec![Point::new(2, 2), Point::new(2, 1)],
+            ],
+            trace.frames
+        );
+        // The rope is left where the trace ends.
+        assert_eq!(trace.frames.last().unwrap(), &rope.knots);
+
+        // Replaying the puzzle example through a trace matches the visited set.
+        let trace = Rope::new(10).trace(head_steps(&parse_input(INPUT)).map(|d| d.delta()));
+        let tail: HashSet<Point> = trace.frames.iter().map(|f| f[9]).collect();
+        assert_eq!(part2(INPUT), tail);
+        assert_eq!(25, trace.frames.len());
+    }
+
+    #[test]
+    fn test_trace_export() {
+        let trace = Rope::new(2).trace([Point::new(1, 0), Point::new(1, 0)]);
+        let mut csv = vec![];
+        trace.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            "step,knot,x,y
+0,0,0,0
+0,1,0,0
+1,0,1,0
+1,1,0,0
+2,0,2,0
+2,1,1,0
+",
+            String::from_utf8(csv).unwrap()
+        );
+
+        let mut jsonl = vec![];
+        trace.write_jsonl(&mut jsonl).unwrap();
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        assert_eq!(6, jsonl.lines().count());
+        assert_eq!(
+            r#"{"step":2,"knot":1,"x":1,"y":0}"#,
+            jsonl.lines().last().unwrap()
+        );
+
+        let trace = Rope::<3>::with_knots(1).trace([Point { coords: [0, 0, -1] }]);
+        let mut csv = vec![];
+        trace.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            "step,knot,x,y,z\n0,0,0,0,0\n1,0,0,0,-1\n",
+            String::from_utf8(csv).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trace_main() {
+        let mut out = vec![];
+        trace_main("UR 1", &args(&["1", "jsonl"]), &mut out).unwrap();
+        assert_eq!(
+            "{\"step\":0,\"knot\":0,\"x\":0,\"y\":0}\n{\"step\":1,\"knot\":0,\"x\":1,\"y\":1}\n",
+            String::from_utf8(out).unwrap()
+        );
+
+        let mut out = vec![];
+        trace_main("R 3", &args(&["2", "csv", "lagging"]), &mut out).unwrap();
+        assert_eq!(
+            "3,1,2,0",
+            String::from_utf8(out).unwrap().lines().last().unwrap()
+        );
+
+        assert!(trace_main("R 3", &args(&["2", "xml"]), &mut vec![]).is_err());
+        assert!(trace_main("R 3", &args(&["2", "csv", "floppy"]), &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_negative_steps() {
+        let movements = parse_input("R 2\nU -1\nL -3\nU 1");
+        assert_eq!(
+            vec![Direction::Right, Direction::Right, Direction::Up],
+            head_steps(&movements).collect::<Vec<_>>()
+        );
+        assert_eq!(2, part1("R 2\nU -1\nL -3\nU 1").len());
     }
 }
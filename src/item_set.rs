@@ -0,0 +1,149 @@
+// Sets of rucksack items stored as a bitmap. Items are ASCII letters and each one has a priority:
+// a-z are 1-26 and A-Z are 27-52. Bit `p` of the bitmap is set when the item with priority `p` is
+// in the set.
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, Error, PartialEq)]
+pub enum ItemError {
+    #[error("{0:?} is not an item, items are ASCII letters")]
+    NotAnItem(char),
+}
+
+// Priority of an item, in [1-52].
+pub fn priority(item: char) -> Result<u32, ItemError> {
+    match item {
+        'a'..='z' => Ok(item as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Ok(item as u32 - 'A' as u32 + 27),
+        _ => Err(ItemError::NotAnItem(item)),
+    }
+}
+
+// Item with the given priority, if there is one.
+pub fn item(priority: u32) -> Option<char> {
+    match priority {
+        1..=26 => char::from_u32('a' as u32 + priority - 1),
+        27..=52 => char::from_u32('A' as u32 + priority - 27),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    pub const fn new() -> Self {
+        ItemSet(0)
+    }
+
+    pub fn from_item(item: char) -> Result<Self, ItemError> {
+        Ok(ItemSet(1 << priority(item)?))
+    }
+
+    // Returns whether the item was newly added.
+    pub fn insert(&mut self, item: char) -> Result<bool, ItemError> {
+        let bit = ItemSet::from_item(item)?.0;
+        let added = self.0 & bit == 0;
+        self.0 |= bit;
+        Ok(added)
+    }
+
+    pub fn contains(&self, item: char) -> bool {
+        ItemSet::from_item(item).is_ok_and(|set| self.0 & set.0 != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        ItemSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        ItemSet(self.0 & other.0)
+    }
+
+    // Priorities of the items in the set, from lowest to highest.
+    pub fn priorities(self) -> impl Iterator<Item = u32> {
+        (1..=52).filter(move |p| self.0 & (1 << p) != 0)
+    }
+
+    // Items in the set, ordered by priority.
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        self.priorities().filter_map(item)
+    }
+}
+
+impl FromStr for ItemSet {
+    type Err = ItemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = ItemSet::new();
+        for c in s.chars() {
+            set.insert(c)?;
+        }
+        Ok(set)
+    }
+}
+
+// Writes the items in priority order, e.g. "bzB".
+impl fmt::Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.iter().try_for_each(|item| write!(f, "{item}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(Ok(1), priority('a'));
+        assert_eq!(Ok(26), priority('z'));
+        assert_eq!(Ok(27), priority('A'));
+        assert_eq!(Ok(52), priority('Z'));
+        assert_eq!(Err(ItemError::NotAnItem('1')), priority('1'));
+        assert_eq!(Err(ItemError::NotAnItem('é')), priority('é'));
+
+        for p in 1..=52 {
+            assert_eq!(Ok(p), priority(item(p).unwrap()));
+        }
+        assert_eq!(None, item(0));
+        assert_eq!(None, item(53));
+    }
+
+    #[test]
+    fn test_item_set() {
+        let a: ItemSet = "vJrwpWtwJgWrhcsFMMfFFhFp".parse().unwrap();
+        let b: ItemSet = "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL".parse().unwrap();
+        assert!(a.contains('p'));
+        assert!(!a.contains('q'));
+        assert!(!a.contains('!'));
+        assert_eq!(
+            vec!['f', 'r', 's', 'F', 'M'],
+            a.intersection(b).iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![6, 18, 19, 32, 39],
+            a.intersection(b).priorities().collect::<Vec<_>>()
+        );
+        assert_eq!(a.len() + b.len() - 5, a.union(b).len());
+
+        let mut set = ItemSet::new();
+        assert!(set.is_empty());
+        assert_eq!(Ok(true), set.insert('Z'));
+        assert_eq!(Ok(false), set.insert('Z'));
+        assert_eq!(Err(ItemError::NotAnItem(' ')), set.insert(' '));
+        assert_eq!(Ok(set), ItemSet::from_item('Z'));
+        assert_eq!(Err(ItemError::NotAnItem('-')), "ab-c".parse::<ItemSet>());
+        assert_eq!("bzB", "BzbbB".parse::<ItemSet>().unwrap().to_string());
+    }
+}
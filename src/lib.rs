@@ -1 +1 @@
-
+pub mod item_set;